@@ -0,0 +1,152 @@
+use std::{error::Error, fmt::Display};
+
+use crate::chrome_version::ChromeVersion;
+
+/// Browser driven by the patched chromedriver (or msedgedriver).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum BrowserFlavor {
+    /// Google Chrome.
+    #[default]
+    Chrome,
+    /// Lightweight `chrome-headless-shell` from Chrome for Testing.
+    ///
+    /// It is always fetched into the cache and always runs headless.
+    ChromeHeadlessShell,
+    /// Microsoft Edge driven by msedgedriver.
+    Edge,
+    /// Brave driven by chromedriver of its Chromium milestone.
+    Brave,
+    /// Vivaldi driven by chromedriver of its Chromium milestone.
+    Vivaldi,
+    /// Opera driven by chromedriver of its Chromium milestone.
+    Opera,
+}
+
+/// Chromium milestones of Vivaldi releases (major, minor, milestone).
+const VIVALDI_CHROMIUM_MILESTONES: &[(u32, u32, u32)] = &[
+    (6, 0, 112),
+    (6, 1, 114),
+    (6, 2, 116),
+    (6, 4, 118),
+    (6, 5, 120),
+    (6, 6, 122),
+    (6, 7, 124),
+    (6, 8, 126),
+    (6, 9, 128),
+    (7, 0, 130),
+    (7, 1, 132),
+    (7, 2, 134),
+];
+
+impl BrowserFlavor {
+    /// Browser name, for Chrome flavors the Chrome for Testing artifact name (e.g. `chrome-headless-shell`).
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Chrome => "chrome",
+            Self::ChromeHeadlessShell => "chrome-headless-shell",
+            Self::Edge => "msedge",
+            Self::Brave => "brave",
+            Self::Vivaldi => "vivaldi",
+            Self::Opera => "opera",
+        }
+    }
+
+    /// Name of the driver executable (without extension), `msedgedriver` for Edge and `chromedriver` otherwise.
+    pub fn driver_name(&self) -> &'static str {
+        match self {
+            Self::Edge => "msedgedriver",
+            _ => "chromedriver",
+        }
+    }
+
+    /// Environment variable with path of the browser executable.
+    pub fn path_env(&self) -> &'static str {
+        match self {
+            Self::Chrome | Self::ChromeHeadlessShell => "CHROME_PATH",
+            Self::Edge => "EDGE_PATH",
+            Self::Brave => "BRAVE_PATH",
+            Self::Vivaldi => "VIVALDI_PATH",
+            Self::Opera => "OPERA_PATH",
+        }
+    }
+
+    /// Returns whether the browser can be fetched from Chrome for Testing.
+    pub fn has_chrome_for_testing(&self) -> bool {
+        matches!(self, Self::Chrome | Self::ChromeHeadlessShell)
+    }
+
+    /// Chromium version the browser of `browser_version` is based on.
+    ///
+    /// For browsers versioned independently of Chromium only the milestone is known
+    /// and the other components are zero. Returns `None` for unknown releases.
+    pub fn chromium_version(&self, browser_version: &ChromeVersion) -> Option<ChromeVersion> {
+        let milestone = match self {
+            Self::Chrome | Self::ChromeHeadlessShell | Self::Edge => return Some(*browser_version),
+            // Brave is versioned as `<milestone>.<brave version>` (e.g. `131.1.73.97`)
+            Self::Brave => browser_version.major,
+            Self::Vivaldi => VIVALDI_CHROMIUM_MILESTONES
+                .iter()
+                .find(|(major, minor, _)| {
+                    *major == browser_version.major && *minor == browser_version.minor
+                })
+                .map(|(_, _, milestone)| *milestone)?,
+            // Opera skipped Chromium 129, since then it is 15 milestones ahead
+            Self::Opera if browser_version.major >= 115 => browser_version.major + 15,
+            Self::Opera if browser_version.major >= 70 => browser_version.major + 14,
+            Self::Opera => return None,
+        };
+        Some(ChromeVersion::new(milestone, 0, 0, 0))
+    }
+
+    /// Key of browser options in capabilities.
+    pub(crate) fn options_key(&self) -> &'static str {
+        match self {
+            Self::Edge => "ms:edgeOptions",
+            _ => "goog:chromeOptions",
+        }
+    }
+}
+
+impl Display for BrowserFlavor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Chromium version of the browser can't be derived from its version.
+#[derive(Debug)]
+pub struct UnknownChromiumVersion {
+    pub browser: BrowserFlavor,
+    pub version: ChromeVersion,
+}
+
+impl Display for UnknownChromiumVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Chromium version of {} {} is unknown, set Chromium milestone explicitly.",
+            self.browser, self.version
+        )
+    }
+}
+
+impl Error for UnknownChromiumVersion {}
+
+/// Chromium milestone was set for a browser that has its own driver releases.
+#[derive(Debug)]
+pub struct ChromiumMilestoneUnsupported {
+    pub browser: BrowserFlavor,
+}
+
+impl Display for ChromiumMilestoneUnsupported {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} is driven by {} of its own version, Chromium milestone can't be set for it.",
+            self.browser,
+            self.browser.driver_name()
+        )
+    }
+}
+
+impl Error for ChromiumMilestoneUnsupported {}
@@ -0,0 +1,304 @@
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+
+use crate::{
+    browser_flavor::BrowserFlavor,
+    chrome_version::ChromeVersion,
+    patch_chromedriver::{PatchConfig, PatchStrategy, TokenReplacement},
+    platform::Platform,
+};
+
+/// On-disk cache holding fetched and patched chromedriver executables.
+///
+/// Executables are laid out per Chrome version and platform,
+/// `<driver>` is [`BrowserFlavor::driver_name`] (`chromedriver` or `msedgedriver`):
+/// ```text
+/// <root>/<chrome version>/<platform>/<driver>
+/// <root>/<chrome version>/<platform>/<driver>_PATCHED
+/// <root>/<chrome version>/<platform>/<driver>_PATCHED.json
+/// <root>/<chrome version>/<platform>/chrome/
+/// <root>/<chrome version>/<platform>/chrome-headless-shell/
+/// <root>/milestone-<milestone>.txt
+/// ```
+///
+/// `milestone-<milestone>.txt` holds the last resolved version of a pinned Chrome milestone,
+/// used when it can't be resolved (e.g. offline).
+///
+/// Each `<platform>` directory has a `.lock` file used to serialize
+/// fetching and patching between processes.
+#[derive(Debug, Clone)]
+pub struct DriverCache {
+    root: PathBuf,
+}
+
+impl DriverCache {
+    /// Creates cache rooted at `root`.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Returns default cache root.
+    ///
+    /// This is `undetected-chromedriver` inside of the user's cache directory
+    /// (`$XDG_CACHE_HOME` or `~/.cache` on Linux).
+    /// If the cache directory can't be determined, system temp directory is used instead.
+    pub fn default_root() -> PathBuf {
+        dirs::cache_dir()
+            .unwrap_or_else(std::env::temp_dir)
+            .join("undetected-chromedriver")
+    }
+
+    /// Directory the cache is rooted at.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Directory holding executables for given Chrome version and platform.
+    pub fn version_dir(&self, chrome_version: &ChromeVersion, platform: Platform) -> PathBuf {
+        self.root
+            .join(chrome_version.to_string())
+            .join(platform.as_str())
+    }
+
+    /// Directory holding fetched `browser`.
+    pub fn browser_dir(
+        &self,
+        browser: BrowserFlavor,
+        chrome_version: &ChromeVersion,
+        platform: Platform,
+    ) -> PathBuf {
+        self.version_dir(chrome_version, platform)
+            .join(browser.as_str())
+    }
+
+    /// Path to the original (unpatched) driver executable of `browser`.
+    pub fn driver_path(
+        &self,
+        browser: BrowserFlavor,
+        chrome_version: &ChromeVersion,
+        platform: Platform,
+    ) -> PathBuf {
+        self.version_dir(chrome_version, platform).join(format!(
+            "{}{}",
+            browser.driver_name(),
+            std::env::consts::EXE_SUFFIX
+        ))
+    }
+
+    /// Path to the patched driver executable of `browser`.
+    pub fn patched_driver_path(
+        &self,
+        browser: BrowserFlavor,
+        chrome_version: &ChromeVersion,
+        platform: Platform,
+    ) -> PathBuf {
+        self.version_dir(chrome_version, platform).join(format!(
+            "{}_PATCHED{}",
+            browser.driver_name(),
+            std::env::consts::EXE_SUFFIX
+        ))
+    }
+
+    /// Path to the last resolved version of Chrome `milestone`.
+    pub fn milestone_version_path(&self, milestone: u32) -> PathBuf {
+        self.root.join(format!("milestone-{milestone}.txt"))
+    }
+
+    /// Reads the last resolved version of Chrome `milestone`, returns `None` if it is missing or invalid.
+    pub(crate) fn read_milestone_version(&self, milestone: u32) -> Option<ChromeVersion> {
+        std::fs::read_to_string(self.milestone_version_path(milestone))
+            .ok()?
+            .trim()
+            .parse()
+            .ok()
+    }
+
+    pub(crate) fn write_milestone_version(
+        &self,
+        milestone: u32,
+        chrome_version: &ChromeVersion,
+    ) -> std::io::Result<()> {
+        write_atomically(&self.milestone_version_path(milestone), |file| {
+            std::io::Write::write_all(file, chrome_version.to_string().as_bytes())
+        })
+    }
+
+    /// Path to metadata describing from what and how the patched executable was made.
+    pub fn patch_metadata_path(
+        &self,
+        browser: BrowserFlavor,
+        chrome_version: &ChromeVersion,
+        platform: Platform,
+    ) -> PathBuf {
+        self.version_dir(chrome_version, platform)
+            .join(format!("{}_PATCHED.json", browser.driver_name()))
+    }
+
+    /// Acquires advisory lock of the directory for given Chrome version and platform.
+    ///
+    /// Waits until other processes holding the lock release it.
+    /// Lock is released when returned [`CacheLock`] is dropped.
+    pub async fn lock(
+        &self,
+        chrome_version: &ChromeVersion,
+        platform: Platform,
+    ) -> std::io::Result<CacheLock> {
+        let dir = self.version_dir(chrome_version, platform);
+        std::fs::create_dir_all(&dir)?;
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(dir.join(".lock"))?;
+        match file.try_lock() {
+            Ok(()) => return Ok(CacheLock { _file: file }),
+            Err(std::fs::TryLockError::WouldBlock) => {
+                tracing::info!("Waiting for another process to prepare chromedriver...");
+            }
+            Err(std::fs::TryLockError::Error(e)) => return Err(e),
+        }
+        let file = tokio::task::spawn_blocking(move || file.lock().map(|_| file))
+            .await
+            .map_err(std::io::Error::other)??;
+        Ok(CacheLock { _file: file })
+    }
+}
+
+/// Advisory lock of a cache directory, released on drop.
+#[derive(Debug)]
+pub struct CacheLock {
+    _file: std::fs::File,
+}
+
+impl Default for DriverCache {
+    fn default() -> Self {
+        Self::new(Self::default_root())
+    }
+}
+
+/// Metadata of the patched executable, it is patched again when they don't match.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct PatchMetadata {
+    /// SHA-256 of the original executable.
+    pub source_sha256: String,
+    pub patch_config: serde_json::Value,
+    pub crate_version: String,
+}
+
+impl PatchMetadata {
+    pub fn new(source_sha256: String, patch_config: &PatchConfig) -> Self {
+        Self {
+            source_sha256,
+            patch_config: patch_config_json(patch_config),
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+
+    /// Reads metadata from `path`, returns `None` if it is missing or invalid.
+    pub fn read(path: &Path) -> Option<Self> {
+        let json = serde_json::from_slice::<serde_json::Value>(&std::fs::read(path).ok()?).ok()?;
+        let field = |name: &str| json[name].as_str().map(str::to_string);
+        Some(Self {
+            source_sha256: field("source_sha256")?,
+            patch_config: json.get("patch_config")?.clone(),
+            crate_version: field("crate_version")?,
+        })
+    }
+
+    pub fn write(&self, path: &Path) -> std::io::Result<()> {
+        let json = serde_json::json!({
+            "source_sha256": self.source_sha256,
+            "patch_config": self.patch_config,
+            "crate_version": self.crate_version,
+        });
+        write_atomically(path, |file| {
+            serde_json::to_writer_pretty(file, &json).map_err(std::io::Error::other)
+        })
+    }
+
+    /// Describes first difference from `other`, if there is any.
+    pub fn mismatch(&self, other: &Self) -> Option<String> {
+        if self.source_sha256 != other.source_sha256 {
+            Some(format!(
+                "original executable changed from {} to {}",
+                other.source_sha256, self.source_sha256
+            ))
+        } else if self.patch_config != other.patch_config {
+            Some(format!(
+                "patch config changed from {} to {}",
+                other.patch_config, self.patch_config
+            ))
+        } else if self.crate_version != other.crate_version {
+            Some(format!(
+                "it was patched by version {} of the crate",
+                other.crate_version
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+/// Describes `config` as JSON, independently of its `Debug` output.
+///
+/// Byte strings of marker patterns are hex encoded.
+fn patch_config_json(config: &PatchConfig) -> serde_json::Value {
+    let strategy = match config.strategy() {
+        PatchStrategy::Rename => "rename",
+        PatchStrategy::NeutralizeBody => "neutralize_body",
+    };
+    let replacement = match config.replacement() {
+        TokenReplacement::Random => serde_json::json!({ "kind": "random" }),
+        TokenReplacement::Seed(seed) => serde_json::json!({ "kind": "seed", "seed": seed }),
+        TokenReplacement::Key(key) => serde_json::json!({ "kind": "key", "key": key }),
+    };
+    let hex = |bytes: &[u8]| bytes.iter().map(|b| format!("{b:02x}")).collect::<String>();
+    let patterns = config
+        .patterns()
+        .iter()
+        .map(|pattern| {
+            serde_json::json!({
+                "prefix": hex(pattern.prefix()),
+                "token_len": pattern.token_len(),
+                "charset": hex(pattern.charset()),
+            })
+        })
+        .collect::<Vec<_>>();
+    serde_json::json!({
+        "strategy": strategy,
+        "replacement": replacement,
+        "patterns": patterns,
+    })
+}
+
+/// Computes SHA-256 (hex encoded) of file at `path`.
+pub(crate) fn sha256_file(path: &Path) -> std::io::Result<String> {
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut std::fs::File::open(path)?, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Writes file at `path` by writing a temporary file next to it first
+/// and renaming it over `path` afterwards, so `path` is never left half-written.
+pub(crate) fn write_atomically<F>(path: &Path, write: F) -> std::io::Result<()>
+where
+    F: FnOnce(&mut std::fs::File) -> std::io::Result<()>,
+{
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(format!(".{}.part", std::process::id()));
+    let tmp_path = path.with_file_name(tmp_name);
+    let res = std::fs::File::create(&tmp_path).and_then(|mut file| {
+        write(&mut file)?;
+        file.sync_all()?;
+        drop(file);
+        std::fs::rename(&tmp_path, path)
+    });
+    if res.is_err() {
+        let _ = std::fs::remove_file(&tmp_path);
+    }
+    res
+}
@@ -1,4 +1,8 @@
-use thirtyfour::{ChromeCapabilities, ChromiumCapabilities, ChromiumLikeCapabilities};
+use thirtyfour::{
+    ChromeCapabilities, ChromiumCapabilities, ChromiumLikeCapabilities, EdgeCapabilities,
+};
+
+use crate::browser_flavor::BrowserFlavor;
 
 /// Default capabilities that are used.
 ///
@@ -12,6 +16,7 @@ use thirtyfour::{ChromeCapabilities, ChromiumCapabilities, ChromiumLikeCapabilit
 /// disable_search_engine_choice_screen: true,
 /// window_position: None
 /// headless: false,
+/// browser_flavor: BrowserFlavor::Chrome,
 /// ```
 pub struct DefaultCapabilitiesBuilder<'a> {
     no_sandbox: bool,
@@ -22,6 +27,7 @@ pub struct DefaultCapabilitiesBuilder<'a> {
     disable_search_engine_choice_screen: bool,
     window_position: Option<(i32, i32)>,
     headless: bool,
+    browser_flavor: BrowserFlavor,
 }
 
 impl<'a> DefaultCapabilitiesBuilder<'a> {
@@ -37,11 +43,20 @@ impl<'a> DefaultCapabilitiesBuilder<'a> {
         caps
     }
 
+    /// Capabilities for Microsoft Edge, use with [`BrowserFlavor::Edge`].
+    pub fn into_edge_caps(self) -> EdgeCapabilities {
+        let mut caps = EdgeCapabilities::new();
+        self.add_caps(&mut caps);
+        caps
+    }
+
     /// Adds capabilities to some type implementing [`ChromiumLikeCapabilities`].
     pub fn add_caps<T>(&self, caps: &mut T)
     where
         T: ChromiumLikeCapabilities,
     {
+        let is_headless_shell = self.browser_flavor == BrowserFlavor::ChromeHeadlessShell;
+
         if self.no_sandbox {
             caps.set_no_sandbox().unwrap();
         }
@@ -62,10 +77,17 @@ impl<'a> DefaultCapabilitiesBuilder<'a> {
         if let Some(user_agent) = self.user_agent {
             let user_agent = format!("user-agent={}", user_agent);
             caps.add_arg(&user_agent).unwrap();
+        } else if is_headless_shell {
+            tracing::warn!(
+                "chrome-headless-shell reports \"HeadlessChrome\" in user-agent, set user-agent to prevent detection!"
+            );
         }
 
         if self.hide_chrome_is_being_controlled {
-            caps.add_arg("disable-infobars").unwrap();
+            // chrome-headless-shell has no infobars
+            if !is_headless_shell {
+                caps.add_arg("disable-infobars").unwrap();
+            }
             caps.add_exclude_switch("enable-automation").unwrap();
         }
 
@@ -75,11 +97,16 @@ impl<'a> DefaultCapabilitiesBuilder<'a> {
         }
 
         if let Some((x, y)) = self.window_position {
-            caps.add_arg(&format!("--window-position={},{}", x, y))
-                .unwrap();
+            if is_headless_shell {
+                tracing::warn!("Window position has no effect in chrome-headless-shell.");
+            } else {
+                caps.add_arg(&format!("--window-position={},{}", x, y))
+                    .unwrap();
+            }
         }
 
-        if self.headless {
+        // chrome-headless-shell is always headless and doesn't know `--headless=new`
+        if self.headless && !is_headless_shell {
             caps.add_arg("--headless=new").unwrap();
         }
     }
@@ -102,6 +129,7 @@ impl<'a> DefaultCapabilitiesBuilder<'a> {
     /// disable_search_engine_choice_screen: true,
     /// window_position: None
     /// headless: false,
+    /// browser_flavor: BrowserFlavor::Chrome,
     /// ```
     pub fn new() -> Self {
         Self::default()
@@ -153,6 +181,14 @@ impl<'a> DefaultCapabilitiesBuilder<'a> {
         self.headless = headless;
         self
     }
+
+    /// Sets browser the capabilities are for.
+    ///
+    /// For [`BrowserFlavor::ChromeHeadlessShell`] options that don't apply to it are skipped.
+    pub fn set_browser_flavor(mut self, browser_flavor: BrowserFlavor) -> Self {
+        self.browser_flavor = browser_flavor;
+        self
+    }
 }
 
 impl<'a> Default for DefaultCapabilitiesBuilder<'a> {
@@ -166,6 +202,7 @@ impl<'a> Default for DefaultCapabilitiesBuilder<'a> {
             disable_search_engine_choice_screen: true,
             window_position: None,
             headless: false,
+            browser_flavor: BrowserFlavor::Chrome,
         }
     }
 }
@@ -175,3 +212,9 @@ impl<'a> From<DefaultCapabilitiesBuilder<'a>> for ChromeCapabilities {
         value.into_chrome_caps()
     }
 }
+
+impl<'a> From<DefaultCapabilitiesBuilder<'a>> for EdgeCapabilities {
+    fn from(value: DefaultCapabilitiesBuilder<'a>) -> Self {
+        value.into_edge_caps()
+    }
+}
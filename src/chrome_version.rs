@@ -0,0 +1,80 @@
+use std::{error::Error, fmt::Display, str::FromStr};
+
+/// Chrome version in `major.minor.build.patch` format (e.g. `131.0.6778.85`).
+///
+/// Versions are ordered numerically, component by component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ChromeVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub build: u32,
+    pub patch: u32,
+}
+
+impl ChromeVersion {
+    pub fn new(major: u32, minor: u32, build: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            build,
+            patch,
+        }
+    }
+
+    /// Returns whether only the milestone is known (e.g. `131.0.0.0`),
+    /// as for browsers based on Chromium or with the milestone set explicitly.
+    pub fn is_milestone_only(&self) -> bool {
+        self.minor == 0 && self.build == 0 && self.patch == 0
+    }
+
+    /// Finds first version in the output of a browser (e.g. `Google Chrome 131.0.6778.85`).
+    pub fn find_in(output: &str) -> Option<Self> {
+        output
+            .split(|ch: char| !ch.is_ascii_digit() && ch != '.')
+            .map(|token| token.trim_matches('.'))
+            .filter(|token| token.contains('.'))
+            .find_map(|token| token.parse().ok())
+    }
+}
+
+impl FromStr for ChromeVersion {
+    type Err = InvalidChromeVersion;
+
+    /// Parses version from `major[.minor[.build[.patch]]]`, missing components are zero.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidChromeVersion(s.to_string());
+        let parts = s
+            .trim()
+            .split('.')
+            .map(|part| part.parse::<u32>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| invalid())?;
+        if parts.len() > 4 {
+            return Err(invalid());
+        }
+        let part = |i: usize| parts.get(i).copied().unwrap_or(0);
+        Ok(Self::new(part(0), part(1), part(2), part(3)))
+    }
+}
+
+impl Display for ChromeVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}.{}.{}.{}",
+            self.major, self.minor, self.build, self.patch
+        )
+    }
+}
+
+/// String is not a valid Chrome version.
+#[derive(Debug)]
+pub struct InvalidChromeVersion(pub String);
+
+impl Display for InvalidChromeVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "\"{}\" is not a valid Chrome version.", self.0)
+    }
+}
+
+impl Error for InvalidChromeVersion {}
@@ -0,0 +1,175 @@
+use std::{
+    io::{Read, Write},
+    net::{SocketAddr, TcpStream},
+    process::{Child, ExitStatus},
+    time::{Duration, Instant},
+};
+
+use crate::spawn_chromedriver::OutputLines;
+
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Running chromedriver, stopped together with browsers it started when dropped.
+///
+/// Chromedriver is asked to shut down via its `/shutdown` endpoint first
+/// and is killed when it doesn't exit in time.
+/// On Linux chromedriver runs in its own process group, which is killed as a whole,
+/// so no browser processes are left behind.
+///
+/// The own process group also means that Ctrl+C in a terminal doesn't reach chromedriver.
+/// Handle the signal (e.g. with `tokio::signal::ctrl_c`) and drop or shut down the process
+/// to stop chromedriver and its browsers.
+///
+/// Dropping blocks for at most the shutdown timeout, use [`ChromeDriverProcess::shutdown`]
+/// to stop chromedriver without blocking the async runtime.
+#[derive(Debug)]
+pub struct ChromeDriverProcess {
+    child: Child,
+    port: u16,
+    output: OutputLines,
+    shutdown_timeout: Duration,
+    stopped: bool,
+}
+
+impl ChromeDriverProcess {
+    pub(crate) fn new(
+        child: Child,
+        port: u16,
+        output: OutputLines,
+        shutdown_timeout: Duration,
+    ) -> Self {
+        Self {
+            child,
+            port,
+            output,
+            shutdown_timeout,
+            stopped: false,
+        }
+    }
+
+    /// Process id of chromedriver.
+    pub fn id(&self) -> u32 {
+        self.child.id()
+    }
+
+    /// Port chromedriver listens on.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Returns at most `n` last lines written by chromedriver.
+    pub fn log_lines(&self, n: usize) -> Vec<String> {
+        self.output.last_lines(n)
+    }
+
+    /// Returns exit status of chromedriver if it has exited.
+    pub fn try_wait(&mut self) -> std::io::Result<Option<ExitStatus>> {
+        self.child.try_wait()
+    }
+
+    /// Asks chromedriver to shut down and kills it (with its process group)
+    /// when it doesn't exit in time.
+    pub async fn shutdown(mut self) -> std::io::Result<ExitStatus> {
+        tokio::task::spawn_blocking(move || self.stop())
+            .await
+            .map_err(std::io::Error::other)?
+    }
+
+    /// Kills chromedriver (with its process group) immediately.
+    pub fn kill(&mut self) -> std::io::Result<()> {
+        self.stopped = true;
+        self.kill_group();
+        if self.child.try_wait()?.is_some() {
+            return Ok(());
+        }
+        self.child.kill()?;
+        self.child.wait()?;
+        Ok(())
+    }
+
+    pub(crate) fn child_mut(&mut self) -> &mut Child {
+        &mut self.child
+    }
+
+    pub(crate) fn output(&self) -> &OutputLines {
+        &self.output
+    }
+
+    fn stop(&mut self) -> std::io::Result<ExitStatus> {
+        self.stopped = true;
+        if let Some(status) = self.child.try_wait()? {
+            // Browsers started by chromedriver can still be running
+            self.kill_group();
+            return Ok(status);
+        }
+        let deadline = Instant::now() + self.shutdown_timeout;
+        if let Err(e) = request_shutdown(self.port, deadline) {
+            tracing::debug!("Shutdown request to chromedriver failed: {e}");
+        }
+        while Instant::now() < deadline {
+            if let Some(status) = self.child.try_wait()? {
+                self.kill_group();
+                return Ok(status);
+            }
+            std::thread::sleep(EXIT_POLL_INTERVAL);
+        }
+        tracing::warn!(
+            "Chromedriver didn't shut down in {:?}, killing it...",
+            self.shutdown_timeout
+        );
+        self.kill_group();
+        let _ = self.child.kill();
+        self.child.wait()
+    }
+
+    /// Kills process group of chromedriver, which includes browsers it started.
+    fn kill_group(&self) {
+        #[cfg(target_os = "linux")]
+        {
+            // Process group id equals to chromedriver pid, it isn't reused while the group exists
+            let pgid = self.child.id() as libc::pid_t;
+            // SAFETY: kill has no memory safety requirements
+            if unsafe { libc::kill(-pgid, libc::SIGKILL) } != 0 {
+                let e = std::io::Error::last_os_error();
+                if e.raw_os_error() != Some(libc::ESRCH) {
+                    tracing::warn!("Can't kill process group of chromedriver: {e}");
+                }
+            }
+        }
+    }
+}
+
+impl Drop for ChromeDriverProcess {
+    fn drop(&mut self) {
+        if !self.stopped {
+            if let Err(e) = self.stop() {
+                tracing::error!("Can't stop chromedriver: {e}");
+            }
+        }
+    }
+}
+
+/// Sends `GET /shutdown` to chromedriver listening on `port`, giving up at `deadline`.
+fn request_shutdown(port: u16, deadline: Instant) -> std::io::Result<()> {
+    let remaining = || {
+        deadline
+            .checked_duration_since(Instant::now())
+            .filter(|remaining| !remaining.is_zero())
+            .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::TimedOut))
+    };
+    let mut stream =
+        TcpStream::connect_timeout(&SocketAddr::from(([127, 0, 0, 1], port)), remaining()?)?;
+    stream.set_write_timeout(Some(remaining()?))?;
+    stream.write_all(
+        format!("GET /shutdown HTTP/1.1\r\nHost: 127.0.0.1:{port}\r\nConnection: close\r\n\r\n")
+            .as_bytes(),
+    )?;
+    // Response is read only to wait until chromedriver handles the request
+    let mut buf = [0; 1024];
+    loop {
+        stream.set_read_timeout(Some(remaining()?))?;
+        if stream.read(&mut buf)? == 0 {
+            return Ok(());
+        }
+    }
+}
@@ -0,0 +1,667 @@
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use thirtyfour::{Capabilities, WebDriver};
+
+use crate::{
+    browser_flavor::{BrowserFlavor, ChromiumMilestoneUnsupported, UnknownChromiumVersion},
+    cache::{sha256_file, DriverCache, PatchMetadata},
+    chrome_version::ChromeVersion,
+    chromedriver_process::ChromeDriverProcess,
+    driver_source::{
+        BrowserDownloadUnsupported, ChromeForTestingSource, DriverSource, EdgeDriverSource,
+    },
+    fetch_browser::{browser_executable, fetch_browser, PinnedChrome},
+    fetch_chromedriver::fetch_chromedriver,
+    find_browser::find_browser,
+    http_client::HttpClient,
+    install_chromedriver::{find_system_chromedriver, install_chromedriver, LocalDriver},
+    patch_chromedriver::{patch_chromedriver, PatchConfig},
+    platform::{Platform, UnsupportedPlatform},
+    port::PortAllocation,
+    spawn_chromedriver::{
+        chromedriver_version, spawn_chromedriver, wait_until_ready, DriverVersionMismatch,
+        LogConfig, SessionNotCreated,
+    },
+    DriverCreationFailed,
+};
+
+/// Builder used to configure how chromedriver is fetched, patched and started.
+///
+/// Default values are:
+/// ```compile_fail
+/// cache: DriverCache::default(),
+/// driver_source: None, // ChromeForTestingSource, or EdgeDriverSource for Edge
+/// http_client: HttpClient::default(),
+/// local_driver: None,
+/// archive_sha256: None,
+/// pinned_chrome: None,
+/// browser_path: None,
+/// chromium_milestone: None,
+/// version_timeout: Duration::from_secs(10),
+/// browser_flavor: BrowserFlavor::Chrome,
+/// patch_config: PatchConfig::default(),
+/// port_allocation: PortAllocation::Os,
+/// ready_timeout: Duration::from_secs(20),
+/// log_config: LogConfig::default(),
+/// shutdown_timeout: Duration::from_secs(5),
+/// num_attempts: 3,
+/// skip_chromedriver_check: false,
+/// ```
+#[derive(Debug, Clone)]
+pub struct ChromeDriverBuilder {
+    cache: DriverCache,
+    driver_source: Option<Arc<dyn DriverSource>>,
+    http_client: HttpClient,
+    local_driver: Option<LocalDriver>,
+    archive_sha256: Option<String>,
+    pinned_chrome: Option<PinnedChrome>,
+    browser_path: Option<PathBuf>,
+    chromium_milestone: Option<u32>,
+    version_timeout: Duration,
+    browser_flavor: BrowserFlavor,
+    patch_config: PatchConfig,
+    port_allocation: PortAllocation,
+    ready_timeout: Duration,
+    log_config: LogConfig,
+    shutdown_timeout: Duration,
+    num_attempts: u8,
+    skip_chromedriver_check: bool,
+}
+
+/// How many times chromedriver is started with a new port when its port is taken.
+const PORT_ATTEMPTS: u8 = 5;
+
+struct DriverPaths {
+    chrome_version: ChromeVersion,
+    platform: Platform,
+    driver: PathBuf,
+    patched: PathBuf,
+    patch_metadata: PathBuf,
+    /// Browser executable set in capabilities.
+    browser: Option<PathBuf>,
+    /// Browser is fetched into the cache when missing.
+    fetch_browser: bool,
+}
+
+impl ChromeDriverBuilder {
+    /// Construct new builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets directory where chromedriver executables are cached.
+    pub fn set_cache_dir(mut self, cache_dir: impl Into<PathBuf>) -> Self {
+        self.cache = DriverCache::new(cache_dir);
+        self
+    }
+
+    /// Sets source from which chromedriver is downloaded.
+    pub fn set_driver_source(mut self, driver_source: impl DriverSource + 'static) -> Self {
+        self.driver_source = Some(Arc::new(driver_source));
+        self
+    }
+
+    /// Sets HTTP client used for version lookups and downloads.
+    ///
+    /// Use it to configure proxy, timeouts, extra root certificates or retries.
+    pub fn set_http_client(mut self, http_client: HttpClient) -> Self {
+        self.http_client = http_client;
+        self
+    }
+
+    /// Sets locally available chromedriver that is installed instead of downloading one.
+    pub fn set_local_driver(mut self, local_driver: Option<LocalDriver>) -> Self {
+        self.local_driver = local_driver;
+        self
+    }
+
+    /// Sets expected SHA-256 (hex encoded) of the chromedriver archive.
+    ///
+    /// Downloaded or local archive that doesn't match it is rejected.
+    /// For [`LocalDriver::Binary`] it is SHA-256 of the executable itself.
+    pub fn set_archive_sha256(mut self, archive_sha256: Option<String>) -> Self {
+        self.archive_sha256 = archive_sha256;
+        self
+    }
+
+    /// Pins Chrome to a version or milestone.
+    ///
+    /// Matching Chrome for Testing browser is fetched into the cache next to chromedriver
+    /// and is used instead of system-installed Chrome.
+    pub fn set_pinned_chrome(mut self, pinned_chrome: Option<PinnedChrome>) -> Self {
+        self.pinned_chrome = pinned_chrome;
+        self
+    }
+
+    /// Sets path of installed browser executable.
+    ///
+    /// When not set, environment variable named by [`BrowserFlavor::path_env`] is used
+    /// or the browser is searched for in known locations.
+    /// Ignored when Chrome is pinned or the browser binary is set in capabilities.
+    pub fn set_browser_path(mut self, browser_path: Option<PathBuf>) -> Self {
+        self.browser_path = browser_path;
+        self
+    }
+
+    /// Sets Chromium milestone of the installed browser instead of deriving it from browser version.
+    ///
+    /// Use it for releases unknown to [`BrowserFlavor::chromium_version`] (e.g. new Vivaldi).
+    /// It can't be set for [`BrowserFlavor::Edge`], which is driven by msedgedriver of its own version.
+    pub fn set_chromium_milestone(mut self, chromium_milestone: Option<u32>) -> Self {
+        self.chromium_milestone = chromium_milestone;
+        self
+    }
+
+    /// Sets how long getting version of installed browser can take.
+    pub fn set_version_timeout(mut self, version_timeout: Duration) -> Self {
+        self.version_timeout = version_timeout;
+        self
+    }
+
+    /// Sets browser that is driven.
+    ///
+    /// [`BrowserFlavor::ChromeHeadlessShell`] is fetched into the cache in the pinned version,
+    /// or in the version of the installed Chrome when no version is pinned.
+    ///
+    /// [`BrowserFlavor::Edge`] is driven by msedgedriver, use capabilities
+    /// from [`DefaultCapabilitiesBuilder::into_edge_caps`](crate::DefaultCapabilitiesBuilder::into_edge_caps) with it.
+    /// Other Chromium-based browsers are driven by chromedriver of their Chromium milestone,
+    /// use capabilities from [`DefaultCapabilitiesBuilder::into_chromium_caps`](crate::DefaultCapabilitiesBuilder::into_chromium_caps) with them.
+    pub fn set_browser_flavor(mut self, browser_flavor: BrowserFlavor) -> Self {
+        self.browser_flavor = browser_flavor;
+        self
+    }
+
+    /// Sets how chromedriver is patched.
+    pub fn set_patch_config(mut self, patch_config: PatchConfig) -> Self {
+        self.patch_config = patch_config;
+        self
+    }
+
+    /// Sets how port chromedriver listens on is chosen.
+    pub fn set_port_allocation(mut self, port_allocation: PortAllocation) -> Self {
+        self.port_allocation = port_allocation;
+        self
+    }
+
+    /// Sets how long to wait for started chromedriver to report it is ready.
+    pub fn set_ready_timeout(mut self, ready_timeout: Duration) -> Self {
+        self.ready_timeout = ready_timeout;
+        self
+    }
+
+    /// Sets chromedriver logging (log file, verbosity) and how many output lines are kept for error reports.
+    pub fn set_log_config(mut self, log_config: LogConfig) -> Self {
+        self.log_config = log_config;
+        self
+    }
+
+    /// Sets how long [`ChromeDriverProcess`] waits for chromedriver to shut down before killing it.
+    pub fn set_shutdown_timeout(mut self, shutdown_timeout: Duration) -> Self {
+        self.shutdown_timeout = shutdown_timeout;
+        self
+    }
+
+    /// Sets how many times creating a session with ready chromedriver is attempted.
+    pub fn set_num_attempts(mut self, num_attempts: u8) -> Self {
+        self.num_attempts = num_attempts;
+        self
+    }
+
+    /// When set, cached chromedriver is used as is without fetching or patching it.
+    pub fn set_skip_chromedriver_check(mut self, skip_chromedriver_check: bool) -> Self {
+        self.skip_chromedriver_check = skip_chromedriver_check;
+        self
+    }
+
+    /// Source from which the driver is downloaded.
+    fn driver_source(&self) -> Arc<dyn DriverSource> {
+        match (&self.driver_source, self.browser_flavor) {
+            (Some(driver_source), _) => driver_source.clone(),
+            (None, BrowserFlavor::Edge) => Arc::new(EdgeDriverSource::default()),
+            (None, _) => Arc::new(ChromeForTestingSource::default()),
+        }
+    }
+
+    /// Cache the driver (and the pinned browser) is stored in, e.g. to look up paths of its files.
+    pub fn cache(&self) -> &DriverCache {
+        &self.cache
+    }
+
+    /// Fetches ChromeDriver executable (if not cached) and patches it to prevent detection.
+    /// Returns path to the patched executable.
+    pub async fn install(&self) -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+        let paths = self.driver_paths(None).await?;
+        self.prepare_driver(&paths).await?;
+        Ok(paths.patched)
+    }
+
+    /// Fetches ChromeDriver executable (if not cached) and patches it to prevent detection.
+    /// Returns a WebDriver instance and handle to chromedriver process.
+    /// If chromedriver fails to start, cached executables are removed and it is tried once more.
+    pub async fn start(
+        &self,
+        capabilities: Capabilities,
+    ) -> Result<(WebDriver, ChromeDriverProcess), Box<dyn Error + Send + Sync>> {
+        let paths = self.driver_paths(Some(&capabilities)).await?;
+        let res = self.start_with_paths(&paths, capabilities.clone()).await;
+        if res.is_ok() {
+            return res;
+        }
+        {
+            let _lock = self
+                .cache
+                .lock(&paths.chrome_version, paths.platform)
+                .await?;
+            let _ = std::fs::remove_file(&paths.driver);
+            let _ = std::fs::remove_file(&paths.patched);
+        }
+        self.start_with_paths(&paths, capabilities).await
+    }
+
+    /// Fetches ChromeDriver executable (if not cached) and patches it to prevent detection.
+    /// Returns a WebDriver instance and handle to chromedriver process.
+    pub async fn try_start(
+        &self,
+        capabilities: Capabilities,
+    ) -> Result<(WebDriver, ChromeDriverProcess), Box<dyn Error + Send + Sync>> {
+        let paths = self.driver_paths(Some(&capabilities)).await?;
+        self.start_with_paths(&paths, capabilities).await
+    }
+
+    /// Resolves paths of chromedriver and browser.
+    ///
+    /// Browser set as `binary` in `capabilities` takes precedence over the browser path.
+    async fn driver_paths(
+        &self,
+        capabilities: Option<&Capabilities>,
+    ) -> Result<DriverPaths, Box<dyn Error + Send + Sync>> {
+        let platform = Platform::current()?;
+        if self.pinned_chrome.is_some() && !self.browser_flavor.has_chrome_for_testing() {
+            return Err(BrowserDownloadUnsupported.into());
+        }
+        if self.chromium_milestone.is_some() && self.browser_flavor == BrowserFlavor::Edge {
+            return Err(ChromiumMilestoneUnsupported {
+                browser: self.browser_flavor,
+            }
+            .into());
+        }
+        let binary =
+            capabilities.and_then(|capabilities| browser_binary(capabilities, self.browser_flavor));
+        if let (Some(binary), Some(_)) = (&binary, self.pinned_chrome) {
+            tracing::warn!(
+                "Browser binary '{}' set in capabilities is used instead of the pinned Chrome.",
+                binary.display()
+            );
+        }
+        let mut installed_chrome = None;
+        let chrome_version = match self.pinned_chrome {
+            Some(PinnedChrome::Version(version)) => version,
+            Some(PinnedChrome::Milestone(milestone)) => {
+                self.pinned_milestone_version(milestone).await?
+            }
+            None => {
+                let browser = find_browser(
+                    self.browser_flavor,
+                    binary.as_deref().or(self.browser_path.as_deref()),
+                    self.version_timeout,
+                )
+                .await?;
+                installed_chrome = Some(browser.path);
+                let chromium_version = match self.chromium_milestone {
+                    Some(milestone) => ChromeVersion::new(milestone, 0, 0, 0),
+                    None => self
+                        .browser_flavor
+                        .chromium_version(&browser.version)
+                        .ok_or(UnknownChromiumVersion {
+                            browser: self.browser_flavor,
+                            version: browser.version,
+                        })?,
+                };
+                if chromium_version != browser.version {
+                    tracing::info!(
+                        "{} {} is based on Chromium {chromium_version}",
+                        self.browser_flavor,
+                        browser.version
+                    );
+                }
+                chromium_version
+            }
+        };
+        let fetch_browser = self.pinned_chrome.is_some()
+            || self.browser_flavor == BrowserFlavor::ChromeHeadlessShell;
+        let browser = if fetch_browser {
+            let executable = browser_executable(self.browser_flavor, platform)
+                .ok_or_else(UnsupportedPlatform::current)?;
+            Some(
+                self.cache
+                    .browser_dir(self.browser_flavor, &chrome_version, platform)
+                    .join(executable),
+            )
+        } else {
+            installed_chrome
+        };
+        Ok(DriverPaths {
+            driver: self
+                .cache
+                .driver_path(self.browser_flavor, &chrome_version, platform),
+            patched: self
+                .cache
+                .patched_driver_path(self.browser_flavor, &chrome_version, platform),
+            patch_metadata: self.cache.patch_metadata_path(
+                self.browser_flavor,
+                &chrome_version,
+                platform,
+            ),
+            browser,
+            fetch_browser,
+            chrome_version,
+            platform,
+        })
+    }
+
+    async fn start_with_paths(
+        &self,
+        paths: &DriverPaths,
+        mut capabilities: Capabilities,
+    ) -> Result<(WebDriver, ChromeDriverProcess), Box<dyn Error + Send + Sync>> {
+        if !self.skip_chromedriver_check {
+            self.prepare_driver(paths).await?;
+        }
+        if let Some(browser) = &paths.browser {
+            set_browser_binary(&mut capabilities, self.browser_flavor, browser);
+        }
+        let mut last_error = None;
+        for _ in 0..PORT_ATTEMPTS {
+            let port = self.port_allocation.free_port()?;
+            tracing::info!("Starting chromedriver on port {port}...");
+            let (chrome_driver_handle, output) =
+                spawn_chromedriver(&paths.patched, port, &self.log_config)?;
+            let mut process =
+                ChromeDriverProcess::new(chrome_driver_handle, port, output, self.shutdown_timeout);
+            match self.connect(&mut process, &capabilities).await {
+                Ok(driver) => return Ok((driver, process)),
+                Err(e) => {
+                    let _ = process.kill();
+                    if !process.output().port_taken() {
+                        return Err(e);
+                    }
+                    tracing::warn!(
+                        "Port {port} was taken by another process, restarting chromedriver on a new port..."
+                    );
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| DriverCreationFailed.into()))
+    }
+
+    /// Creates WebDriver session with chromedriver listening on `port`.
+    async fn connect(
+        &self,
+        process: &mut ChromeDriverProcess,
+        capabilities: &Capabilities,
+    ) -> Result<WebDriver, Box<dyn Error + Send + Sync>> {
+        let port = process.port();
+        let output = process.output().clone();
+        let chrome_driver_handle = process.child_mut();
+        wait_until_ready(chrome_driver_handle, &output, port, self.ready_timeout).await?;
+        let mut error = String::new();
+        for _ in 0..self.num_attempts {
+            match WebDriver::new(&format!("http://127.0.0.1:{}", port), capabilities.clone()).await
+            {
+                Ok(driver) => match chrome_driver_handle.try_wait()? {
+                    // Session could be created by another chromedriver listening on the port
+                    Some(status) => {
+                        let _ = driver.quit().await;
+                        return Err(output.exited(status).await.into());
+                    }
+                    None => return Ok(driver),
+                },
+                Err(e) => {
+                    tracing::error!("Got error when initializing chrome: {e:#?}");
+                    if let Some(status) = chrome_driver_handle.try_wait()? {
+                        return Err(output.exited(status).await.into());
+                    }
+                    error = e.to_string();
+                    tokio::time::sleep(Duration::from_millis(250)).await
+                }
+            }
+        }
+        Err(SessionNotCreated {
+            error,
+            output: output.lines(),
+        }
+        .into())
+    }
+
+    /// Fetches and patches chromedriver while holding the cache lock,
+    /// so that concurrent processes don't do the same work at the same time.
+    async fn prepare_driver(
+        &self,
+        paths: &DriverPaths,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let _lock = self
+            .cache
+            .lock(&paths.chrome_version, paths.platform)
+            .await?;
+        self.prepare_driver_locked(paths).await
+    }
+
+    async fn prepare_driver_locked(
+        &self,
+        paths: &DriverPaths,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let system_fallback = self.local_driver.is_none()
+            && self.browser_flavor != BrowserFlavor::Edge
+            && !paths.platform.has_chrome_for_testing();
+        // Drivers installed from local files are checked when installed,
+        // as fetching them again wouldn't change anything
+        if paths.driver.exists() && self.local_driver.is_none() && !system_fallback {
+            self.remove_stale_driver(paths).await?;
+        }
+        if paths.driver.exists() {
+            tracing::info!("ChromeDriver already exists!");
+        } else if let Some(local_driver) = &self.local_driver {
+            install_chromedriver(local_driver, self.archive_sha256.as_deref(), &paths.driver)?;
+            self.check_installed_driver(paths).await?;
+        } else if system_fallback {
+            tracing::warn!(
+                "There is no Chrome for Testing build for {}, falling back to system chromedriver.",
+                paths.platform
+            );
+            let system_driver =
+                find_system_chromedriver().ok_or_else(UnsupportedPlatform::current)?;
+            install_chromedriver(&LocalDriver::Binary(system_driver), None, &paths.driver)?;
+            self.check_installed_driver(paths).await?;
+        } else {
+            tracing::info!("ChromeDriver does not exist! Fetching...");
+            fetch_chromedriver(
+                &self.http_client,
+                self.driver_source().as_ref(),
+                &paths.chrome_version,
+                paths.platform,
+                self.archive_sha256.as_deref(),
+                &paths.driver,
+            )
+            .await?;
+        }
+        if let Some(browser) = paths
+            .browser
+            .as_ref()
+            .filter(|browser| paths.fetch_browser && !browser.exists())
+        {
+            tracing::info!("{} does not exist! Fetching...", self.browser_flavor);
+            fetch_browser(
+                &self.http_client,
+                self.driver_source().as_ref(),
+                self.browser_flavor,
+                &paths.chrome_version,
+                paths.platform,
+                &self
+                    .cache
+                    .browser_dir(self.browser_flavor, &paths.chrome_version, paths.platform),
+            )
+            .await?;
+            if !browser.exists() {
+                return Err(
+                    format!("Browser executable '{}' not found.", browser.display()).into(),
+                );
+            }
+        }
+        let expected_metadata = PatchMetadata::new(sha256_file(&paths.driver)?, &self.patch_config);
+        let patched_exists = paths.patched.exists();
+        let outdated_reason = match PatchMetadata::read(&paths.patch_metadata) {
+            _ if !patched_exists => None,
+            Some(metadata) => expected_metadata.mismatch(&metadata),
+            None => Some("its metadata are missing".to_string()),
+        };
+        if patched_exists && outdated_reason.is_none() {
+            tracing::info!("Detected patched chromedriver executable!");
+        } else {
+            if let Some(reason) = outdated_reason {
+                tracing::info!("Patched chromedriver is outdated, {reason}. Patching again...");
+            }
+            let report = patch_chromedriver(&paths.driver, &paths.patched, &self.patch_config)?;
+            tracing::info!(
+                "Patched chromedriver {} -> {}",
+                report.input_sha256,
+                report.output_sha256
+            );
+            PatchMetadata::new(report.input_sha256, &self.patch_config)
+                .write(&paths.patch_metadata)?;
+        }
+        Ok(())
+    }
+
+    /// Resolves latest Chrome version of pinned `milestone` and records it in the cache.
+    ///
+    /// When it can't be resolved (e.g. offline), the last recorded version is used.
+    async fn pinned_milestone_version(
+        &self,
+        milestone: u32,
+    ) -> Result<ChromeVersion, Box<dyn Error + Send + Sync>> {
+        match self
+            .driver_source()
+            .milestone_version(&self.http_client, milestone)
+            .await
+        {
+            Ok(version) => {
+                if let Err(e) = self.cache.write_milestone_version(milestone, &version) {
+                    tracing::warn!("Can't record version of Chrome {milestone}: {e}");
+                }
+                Ok(version)
+            }
+            Err(e) => match self.cache.read_milestone_version(milestone) {
+                Some(version) => {
+                    tracing::warn!(
+                        "Can't resolve latest version of Chrome {milestone} ({e}), using {version} resolved before."
+                    );
+                    Ok(version)
+                }
+                None => Err(e),
+            },
+        }
+    }
+
+    /// Removes cached chromedriver (and its patched copy) when its milestone
+    /// doesn't match Chrome, so that it is fetched again.
+    async fn remove_stale_driver(
+        &self,
+        paths: &DriverPaths,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let reason = match chromedriver_version(&paths.driver, self.version_timeout).await {
+            Ok(driver_version) if driver_version.major == paths.chrome_version.major => {
+                return Ok(())
+            }
+            Ok(driver_version) => format!(
+                "its version {driver_version} doesn't match Chrome {}",
+                paths.chrome_version
+            ),
+            Err(e) => format!("its version can't be determined: {e}"),
+        };
+        tracing::warn!(
+            "Cached chromedriver '{}' is stale, {reason}. Refreshing...",
+            paths.driver.display()
+        );
+        for path in [&paths.driver, &paths.patched] {
+            match std::fs::remove_file(path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that chromedriver installed from a local file matches Chrome,
+    /// removing it from the cache when it doesn't.
+    async fn check_installed_driver(
+        &self,
+        paths: &DriverPaths,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let result = match chromedriver_version(&paths.driver, self.version_timeout).await {
+            Ok(driver_version) if driver_version.major == paths.chrome_version.major => {
+                return Ok(())
+            }
+            Ok(driver_version) => Err(DriverVersionMismatch {
+                driver_version,
+                chrome_version: paths.chrome_version,
+            }
+            .into()),
+            Err(e) => Err(e),
+        };
+        std::fs::remove_file(&paths.driver)?;
+        result
+    }
+}
+
+impl Default for ChromeDriverBuilder {
+    fn default() -> Self {
+        Self {
+            cache: DriverCache::default(),
+            driver_source: None,
+            http_client: HttpClient::default(),
+            local_driver: None,
+            archive_sha256: None,
+            pinned_chrome: None,
+            browser_path: None,
+            chromium_milestone: None,
+            version_timeout: Duration::from_secs(10),
+            browser_flavor: BrowserFlavor::default(),
+            patch_config: PatchConfig::default(),
+            port_allocation: PortAllocation::default(),
+            ready_timeout: Duration::from_secs(20),
+            log_config: LogConfig::default(),
+            shutdown_timeout: Duration::from_secs(5),
+            num_attempts: 3,
+            skip_chromedriver_check: false,
+        }
+    }
+}
+
+/// Returns browser executable set in options of `flavor` in `capabilities`.
+fn browser_binary(capabilities: &Capabilities, flavor: BrowserFlavor) -> Option<PathBuf> {
+    capabilities
+        .get(flavor.options_key())?
+        .get("binary")?
+        .as_str()
+        .map(PathBuf::from)
+}
+
+/// Points options of `flavor` in `capabilities` to `browser` executable,
+/// unless they already point to one.
+fn set_browser_binary(capabilities: &mut Capabilities, flavor: BrowserFlavor, browser: &Path) {
+    let options = capabilities
+        .entry(flavor.options_key())
+        .or_insert_with(|| serde_json::json!({}));
+    if let Some(options) = options.as_object_mut() {
+        options
+            .entry("binary")
+            .or_insert_with(|| browser.to_string_lossy().into_owned().into());
+    }
+}
@@ -1,8 +1,8 @@
-use std::{error::Error, process::Child, time::Duration};
+use std::{error::Error, time::Duration};
 
 use thirtyfour::{Capabilities, WebDriver};
 
-use crate::{chrome, chrome_with_capabilities};
+use crate::{chrome, chrome_with_capabilities, ChromeDriverProcess};
 
 #[async_trait::async_trait]
 pub trait Chrome: Sized {
@@ -10,22 +10,22 @@ pub trait Chrome: Sized {
     ///
     /// Panics if initialization failed!
     /// For non-panicking version use [`chrome`] function.
-    async fn new() -> (Self, Child);
+    async fn new() -> (Self, ChromeDriverProcess);
     /// Initializes chromedriver with specified capabilities.
     ///
     /// Panics if initialization failed!
     /// For non-panicking version use [`chrome_with_capabilities`] function.
-    async fn new_with_capabilities(capabilities: Capabilities) -> (WebDriver, Child);
+    async fn new_with_capabilities(capabilities: Capabilities) -> (WebDriver, ChromeDriverProcess);
     async fn goto(&self, url: &str) -> Result<(), Box<dyn Error + Send + Sync>>;
 }
 
 #[async_trait::async_trait]
 impl Chrome for WebDriver {
-    async fn new() -> (WebDriver, Child) {
+    async fn new() -> (WebDriver, ChromeDriverProcess) {
         chrome().await.expect("Failed to initialize chromedriver.")
     }
 
-    async fn new_with_capabilities(capabilities: Capabilities) -> (WebDriver, Child) {
+    async fn new_with_capabilities(capabilities: Capabilities) -> (WebDriver, ChromeDriverProcess) {
         chrome_with_capabilities(capabilities)
             .await
             .expect("Failed to initialize chromedriver.")
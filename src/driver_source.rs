@@ -0,0 +1,387 @@
+use std::{error::Error, fmt::Display};
+
+use crate::{
+    browser_flavor::BrowserFlavor,
+    chrome_version::ChromeVersion,
+    http_client::HttpClient,
+    platform::{Platform, UnsupportedPlatform},
+};
+
+/// Source from which chromedriver archives are downloaded.
+///
+/// Implement this trait to fetch chromedriver from somewhere else
+/// than the default [`ChromeForTestingSource`] (e.g. an artifact mirror).
+#[async_trait::async_trait]
+pub trait DriverSource: std::fmt::Debug + Send + Sync {
+    /// Resolves URL of chromedriver zip archive matching `chrome_version` on `platform`.
+    async fn driver_url(
+        &self,
+        client: &HttpClient,
+        chrome_version: &ChromeVersion,
+        platform: Platform,
+    ) -> Result<String, Box<dyn Error + Send + Sync>>;
+
+    /// Resolves latest Chrome version of `milestone`.
+    ///
+    /// Needed only for pinning Chrome to a milestone.
+    async fn milestone_version(
+        &self,
+        _client: &HttpClient,
+        _milestone: u32,
+    ) -> Result<ChromeVersion, Box<dyn Error + Send + Sync>> {
+        Err(BrowserDownloadUnsupported.into())
+    }
+
+    /// Resolves URL of zip archive with `browser` of exactly `chrome_version` on `platform`.
+    ///
+    /// Needed only for pinning Chrome and for `chrome-headless-shell`.
+    async fn browser_url(
+        &self,
+        _client: &HttpClient,
+        _browser: BrowserFlavor,
+        _chrome_version: &ChromeVersion,
+        _platform: Platform,
+    ) -> Result<String, Box<dyn Error + Send + Sync>> {
+        Err(BrowserDownloadUnsupported.into())
+    }
+}
+
+/// Chrome for Testing endpoints together with the legacy chromedriver storage
+/// used for Chrome older than 114.
+///
+/// Chromedriver for exactly the installed Chrome version is preferred,
+/// latest chromedriver of the same milestone is used when there is none
+/// or when only the milestone is known (see [`ChromeVersion::is_milestone_only`]).
+///
+/// URL templates can contain `{version}` and `{platform}` placeholders.
+///
+/// Default values are:
+/// ```compile_fail
+/// known_good_versions_url: "https://googlechromelabs.github.io/chrome-for-testing/known-good-versions-with-downloads.json",
+/// milestone_versions_url: "https://googlechromelabs.github.io/chrome-for-testing/latest-versions-per-milestone.json",
+/// download_url_template: "https://storage.googleapis.com/chrome-for-testing-public/{version}/{platform}/chromedriver-{platform}.zip",
+/// browser_download_url_template: "https://storage.googleapis.com/chrome-for-testing-public/{version}/{platform}/chrome-{platform}.zip",
+/// headless_shell_download_url_template: "https://storage.googleapis.com/chrome-for-testing-public/{version}/{platform}/chrome-headless-shell-{platform}.zip",
+/// legacy_release_url_template: "https://chromedriver.storage.googleapis.com/LATEST_RELEASE_{version}",
+/// legacy_download_url_template: "https://chromedriver.storage.googleapis.com/{version}/chromedriver_{platform}.zip",
+/// ```
+#[derive(Debug, Clone)]
+pub struct ChromeForTestingSource {
+    known_good_versions_url: String,
+    milestone_versions_url: String,
+    download_url_template: String,
+    browser_download_url_template: String,
+    headless_shell_download_url_template: String,
+    legacy_release_url_template: String,
+    legacy_download_url_template: String,
+}
+
+impl ChromeForTestingSource {
+    /// Construct new source with default Google endpoints.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets URL of JSON with all known good versions and their downloads.
+    pub fn set_known_good_versions_url(
+        mut self,
+        known_good_versions_url: impl Into<String>,
+    ) -> Self {
+        self.known_good_versions_url = known_good_versions_url.into();
+        self
+    }
+
+    /// Sets URL of JSON with latest versions per milestone.
+    pub fn set_milestone_versions_url(mut self, milestone_versions_url: impl Into<String>) -> Self {
+        self.milestone_versions_url = milestone_versions_url.into();
+        self
+    }
+
+    /// Sets template of chromedriver archive URL.
+    pub fn set_download_url_template(mut self, download_url_template: impl Into<String>) -> Self {
+        self.download_url_template = download_url_template.into();
+        self
+    }
+
+    /// Sets template of Chrome browser archive URL.
+    pub fn set_browser_download_url_template(
+        mut self,
+        browser_download_url_template: impl Into<String>,
+    ) -> Self {
+        self.browser_download_url_template = browser_download_url_template.into();
+        self
+    }
+
+    /// Sets template of `chrome-headless-shell` archive URL.
+    pub fn set_headless_shell_download_url_template(
+        mut self,
+        headless_shell_download_url_template: impl Into<String>,
+    ) -> Self {
+        self.headless_shell_download_url_template = headless_shell_download_url_template.into();
+        self
+    }
+
+    /// Sets template of URL returning latest chromedriver version for Chrome older than 114.
+    ///
+    /// `{version}` is replaced with Chrome milestone.
+    pub fn set_legacy_release_url_template(
+        mut self,
+        legacy_release_url_template: impl Into<String>,
+    ) -> Self {
+        self.legacy_release_url_template = legacy_release_url_template.into();
+        self
+    }
+
+    /// Sets template of chromedriver archive URL for Chrome older than 114.
+    ///
+    /// `{platform}` is replaced with legacy platform name (`linux64`, `mac64`, `mac_arm64` or `win32`).
+    pub fn set_legacy_download_url_template(
+        mut self,
+        legacy_download_url_template: impl Into<String>,
+    ) -> Self {
+        self.legacy_download_url_template = legacy_download_url_template.into();
+        self
+    }
+}
+
+impl Default for ChromeForTestingSource {
+    fn default() -> Self {
+        Self {
+            known_good_versions_url: "https://googlechromelabs.github.io/chrome-for-testing/known-good-versions-with-downloads.json".to_string(),
+            milestone_versions_url: "https://googlechromelabs.github.io/chrome-for-testing/latest-versions-per-milestone.json".to_string(),
+            download_url_template: "https://storage.googleapis.com/chrome-for-testing-public/{version}/{platform}/chromedriver-{platform}.zip".to_string(),
+            browser_download_url_template: "https://storage.googleapis.com/chrome-for-testing-public/{version}/{platform}/chrome-{platform}.zip".to_string(),
+            headless_shell_download_url_template: "https://storage.googleapis.com/chrome-for-testing-public/{version}/{platform}/chrome-headless-shell-{platform}.zip".to_string(),
+            legacy_release_url_template: "https://chromedriver.storage.googleapis.com/LATEST_RELEASE_{version}".to_string(),
+            legacy_download_url_template: "https://chromedriver.storage.googleapis.com/{version}/chromedriver_{platform}.zip".to_string(),
+        }
+    }
+}
+
+impl ChromeForTestingSource {
+    /// Looks up exactly `chrome_version` among known good versions having chromedriver for `platform`.
+    async fn find_known_good_version(
+        &self,
+        client: &HttpClient,
+        chrome_version: &ChromeVersion,
+        platform: Platform,
+    ) -> Result<Option<String>, Box<dyn Error + Send + Sync>> {
+        let body = client.get_bytes(&self.known_good_versions_url).await?;
+        let json = serde_json::from_slice::<serde_json::Value>(&body)?;
+        let chrome_version = chrome_version.to_string();
+        let version = json["versions"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|version| version["version"].as_str() == Some(chrome_version.as_str()))
+            .filter(|version| {
+                version["downloads"]["chromedriver"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .any(|download| download["platform"].as_str() == Some(platform.as_str()))
+            })
+            .map(|_| chrome_version);
+        Ok(version)
+    }
+
+    /// Looks up latest version of `milestone`.
+    async fn find_milestone_version(
+        &self,
+        client: &HttpClient,
+        milestone: u32,
+    ) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let body = client.get_bytes(&self.milestone_versions_url).await?;
+        let json = serde_json::from_slice::<serde_json::Value>(&body)?;
+        let version = json["milestones"][milestone.to_string()]["version"]
+            .as_str()
+            .ok_or(ChromeDriverFetchError)?;
+        Ok(version.to_string())
+    }
+}
+
+#[async_trait::async_trait]
+impl DriverSource for ChromeForTestingSource {
+    async fn driver_url(
+        &self,
+        client: &HttpClient,
+        chrome_version: &ChromeVersion,
+        platform: Platform,
+    ) -> Result<String, Box<dyn Error + Send + Sync>> {
+        if chrome_version.major >= 114 {
+            // Prefer chromedriver built for exactly the installed version
+            let exact_version = if chrome_version.is_milestone_only() {
+                // No such build exists, latest of the milestone is used right away
+                Ok(None)
+            } else {
+                self.find_known_good_version(client, chrome_version, platform)
+                    .await
+            };
+            let version = match exact_version {
+                Ok(Some(version)) => version,
+                Ok(None) => {
+                    tracing::info!(
+                        "No chromedriver for exactly {chrome_version}, using latest of milestone {}.",
+                        chrome_version.major
+                    );
+                    self.find_milestone_version(client, chrome_version.major)
+                        .await?
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to look up known good versions: {e}");
+                    self.find_milestone_version(client, chrome_version.major)
+                        .await?
+                }
+            };
+
+            Ok(fill_template(
+                &self.download_url_template,
+                &version,
+                platform.as_str(),
+            ))
+        } else {
+            let body = client
+                .get_text(&fill_template(
+                    &self.legacy_release_url_template,
+                    &chrome_version.major.to_string(),
+                    platform.as_str(),
+                ))
+                .await?;
+            let legacy_platform = match platform {
+                Platform::Linux64 => "linux64",
+                Platform::MacX64 => "mac64",
+                Platform::MacArm64 => "mac_arm64",
+                Platform::Win32 | Platform::Win64 => "win32",
+                Platform::LinuxArm64 => return Err(UnsupportedPlatform::current().into()),
+            };
+            Ok(fill_template(
+                &self.legacy_download_url_template,
+                body.trim(),
+                legacy_platform,
+            ))
+        }
+    }
+
+    async fn milestone_version(
+        &self,
+        client: &HttpClient,
+        milestone: u32,
+    ) -> Result<ChromeVersion, Box<dyn Error + Send + Sync>> {
+        Ok(self
+            .find_milestone_version(client, milestone)
+            .await?
+            .parse()?)
+    }
+
+    async fn browser_url(
+        &self,
+        _client: &HttpClient,
+        browser: BrowserFlavor,
+        chrome_version: &ChromeVersion,
+        platform: Platform,
+    ) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let template = match browser {
+            BrowserFlavor::Chrome => &self.browser_download_url_template,
+            BrowserFlavor::ChromeHeadlessShell => &self.headless_shell_download_url_template,
+            _ => return Err(BrowserDownloadUnsupported.into()),
+        };
+        Ok(fill_template(
+            template,
+            &chrome_version.to_string(),
+            platform.as_str(),
+        ))
+    }
+}
+
+/// Microsoft Edge WebDriver (msedgedriver) downloads.
+///
+/// msedgedriver is published for every Edge version,
+/// so driver of exactly the installed version is downloaded.
+///
+/// URL template can contain `{version}` and `{platform}` placeholders,
+/// `{platform}` is replaced with Edge platform name
+/// (`linux64`, `mac64`, `mac64_m1`, `win32` or `win64`).
+///
+/// Default values are:
+/// ```compile_fail
+/// download_url_template: "https://msedgedriver.microsoft.com/{version}/edgedriver_{platform}.zip",
+/// ```
+#[derive(Debug, Clone)]
+pub struct EdgeDriverSource {
+    download_url_template: String,
+}
+
+impl EdgeDriverSource {
+    /// Construct new source with default Microsoft endpoint.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets template of msedgedriver archive URL.
+    pub fn set_download_url_template(mut self, download_url_template: impl Into<String>) -> Self {
+        self.download_url_template = download_url_template.into();
+        self
+    }
+}
+
+impl Default for EdgeDriverSource {
+    fn default() -> Self {
+        Self {
+            download_url_template:
+                "https://msedgedriver.microsoft.com/{version}/edgedriver_{platform}.zip".to_string(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl DriverSource for EdgeDriverSource {
+    async fn driver_url(
+        &self,
+        _client: &HttpClient,
+        edge_version: &ChromeVersion,
+        platform: Platform,
+    ) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let edge_platform = match platform {
+            Platform::Linux64 => "linux64",
+            Platform::MacX64 => "mac64",
+            Platform::MacArm64 => "mac64_m1",
+            Platform::Win32 => "win32",
+            Platform::Win64 => "win64",
+            Platform::LinuxArm64 => return Err(UnsupportedPlatform::current().into()),
+        };
+        Ok(fill_template(
+            &self.download_url_template,
+            &edge_version.to_string(),
+            edge_platform,
+        ))
+    }
+}
+
+fn fill_template(template: &str, version: &str, platform: &str) -> String {
+    template
+        .replace("{version}", version)
+        .replace("{platform}", platform)
+}
+
+#[derive(Debug)]
+struct ChromeDriverFetchError;
+
+impl Display for ChromeDriverFetchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "No chromedriver version was found.")
+    }
+}
+
+impl Error for ChromeDriverFetchError {}
+
+/// Driver source doesn't provide browser downloads.
+#[derive(Debug)]
+pub struct BrowserDownloadUnsupported;
+
+impl Display for BrowserDownloadUnsupported {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Driver source doesn't provide browser downloads.")
+    }
+}
+
+impl Error for BrowserDownloadUnsupported {}
@@ -0,0 +1,85 @@
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    browser_flavor::BrowserFlavor, chrome_version::ChromeVersion, driver_source::DriverSource,
+    http_client::HttpClient, platform::Platform,
+};
+
+/// Chrome version the browser is pinned to.
+///
+/// Pinned Chrome for Testing browser is fetched next to the driver
+/// and used instead of system-installed Chrome.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PinnedChrome {
+    /// Exactly this version.
+    Version(ChromeVersion),
+    /// Latest version of this milestone.
+    Milestone(u32),
+}
+
+/// Fetches `browser` of exactly `chrome_version` from `source` and extracts it to `out_dir`.
+pub async fn fetch_browser(
+    client: &HttpClient,
+    source: &dyn DriverSource,
+    browser: BrowserFlavor,
+    chrome_version: &ChromeVersion,
+    platform: Platform,
+    out_dir: &Path,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let browser_url = source
+        .browser_url(client, browser, chrome_version, platform)
+        .await?;
+    tracing::info!("Fetching {browser} from {browser_url}");
+
+    let body = client.get_bytes(&browser_url).await?;
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(body))?;
+
+    // Extract next to `out_dir` first, so a partially extracted browser is never used
+    let mut tmp_name = out_dir.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(format!(".{}.part", std::process::id()));
+    let tmp_dir = out_dir.with_file_name(tmp_name);
+    let res = archive
+        .extract(&tmp_dir)
+        .map_err(std::io::Error::other)
+        .and_then(|_| remove_stale_dir(out_dir))
+        .and_then(|_| std::fs::rename(&tmp_dir, out_dir));
+    if res.is_err() {
+        let _ = std::fs::remove_dir_all(&tmp_dir);
+    }
+    res?;
+    tracing::info!(
+        "{browser} {chrome_version} extracted to '{}'",
+        out_dir.display()
+    );
+    Ok(())
+}
+
+/// Removes `dir` left by an interrupted or incomplete extraction, so it can be replaced.
+fn remove_stale_dir(dir: &Path) -> std::io::Result<()> {
+    match std::fs::remove_dir_all(dir) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Path of `browser` executable relative to the directory the browser archive was extracted to.
+///
+/// Returns `None` when there are no Chrome for Testing builds of `browser` for `platform`.
+pub fn browser_executable(browser: BrowserFlavor, platform: Platform) -> Option<PathBuf> {
+    if !platform.has_chrome_for_testing() {
+        return None;
+    }
+    let executable = match (browser, platform) {
+        (BrowserFlavor::Chrome, Platform::MacX64 | Platform::MacArm64) => {
+            "Google Chrome for Testing.app/Contents/MacOS/Google Chrome for Testing"
+        }
+        (BrowserFlavor::Chrome, _) => "chrome",
+        (BrowserFlavor::ChromeHeadlessShell, _) => "chrome-headless-shell",
+        _ => return None,
+    };
+    let executable = format!("{executable}{}", std::env::consts::EXE_SUFFIX);
+    Some(PathBuf::from(format!("{}-{}", browser.as_str(), platform.as_str())).join(executable))
+}
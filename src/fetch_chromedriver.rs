@@ -1,93 +1,116 @@
-use std::{error::Error, fmt::Display};
+use std::{
+    error::Error,
+    fmt::Display,
+    io::{Read, Seek},
+    path::Path,
+};
 
-use crate::get_chrome_version::get_chrome_version;
+use sha2::{Digest, Sha256};
 
-pub async fn fetch_chromedriver() -> Result<(), Box<dyn Error + Send + Sync>> {
-    let os = std::env::consts::OS;
-    let client = reqwest::Client::new();
+use crate::{
+    cache::write_atomically, chrome_version::ChromeVersion, driver_source::DriverSource,
+    http_client::HttpClient, platform::Platform,
+};
 
-    let installed_version = get_chrome_version(os).await?;
-    let chromedriver_url: String;
-    if installed_version.as_str() >= "114" {
-        // Fetch the correct version
-        let url = "https://googlechromelabs.github.io/chrome-for-testing/latest-versions-per-milestone.json";
-        let resp = client.get(url).send().await?;
-        let body = resp.bytes().await?;
-        let json = serde_json::from_slice::<serde_json::Value>(&body)?;
-        let version = json["milestones"][installed_version]["version"]
-            .as_str()
-            .ok_or(ChromeDriverFetchError)?;
+/// Fetches chromedriver matching `installed_version` of Chrome from `source`
+/// and writes it to `out_path`.
+///
+/// If `expected_sha256` is set, downloaded archive has to match it.
+pub async fn fetch_chromedriver(
+    client: &HttpClient,
+    source: &dyn DriverSource,
+    installed_version: &ChromeVersion,
+    platform: Platform,
+    expected_sha256: Option<&str>,
+    out_path: &Path,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let chromedriver_url = source
+        .driver_url(client, installed_version, platform)
+        .await?;
+    tracing::info!("Fetching chromedriver from {chromedriver_url}");
 
-        // Fetch the chromedriver binary
-        chromedriver_url = match os {
-            "linux" => format!(
-                "https://storage.googleapis.com/chrome-for-testing-public/{}/{}/{}",
-                version, "linux64", "chromedriver-linux64.zip"
-            ),
-            "macos" => format!(
-                "https://storage.googleapis.com/chrome-for-testing-public/{}/{}/{}",
-                version, "mac-x64", "chromedriver-mac-x64.zip"
-            ),
-            "windows" => format!(
-                "https://storage.googleapis.com/chrome-for-testing-public/{}/{}/{}",
-                version, "win64", "chromedriver-win64.zip"
-            ),
-            _ => panic!("Unsupported OS!"),
-        };
-    } else {
-        let resp = client
-            .get(format!(
-                "https://chromedriver.storage.googleapis.com/LATEST_RELEASE_{}",
-                installed_version
-            ))
-            .send()
-            .await?;
-        let body = resp.text().await?;
-        chromedriver_url = match os {
-            "linux" => format!(
-                "https://chromedriver.storage.googleapis.com/{}/chromedriver_linux64.zip",
-                body
-            ),
-            "windows" => format!(
-                "https://chromedriver.storage.googleapis.com/{}/chromedriver_win32.zip",
-                body
-            ),
-            "macos" => format!(
-                "https://chromedriver.storage.googleapis.com/{}/chromedriver_mac64.zip",
-                body
-            ),
-            _ => panic!("Unsupported OS!"),
-        };
-    }
+    let body = client.get_bytes(&chromedriver_url).await?;
+    verify_sha256(&body, expected_sha256)?;
+
+    extract_chromedriver(std::io::Cursor::new(body), out_path)
+}
 
-    let resp = client.get(&chromedriver_url).send().await?;
-    let body = resp.bytes().await?;
+/// Checks that SHA-256 of `bytes` matches `expected_sha256` (hex encoded), if set.
+pub fn verify_sha256(
+    bytes: &[u8],
+    expected_sha256: Option<&str>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let Some(expected) = expected_sha256 else {
+        return Ok(());
+    };
+    let actual = format!("{:x}", Sha256::digest(bytes));
+    if !actual.eq_ignore_ascii_case(expected.trim()) {
+        return Err(ChecksumMismatch {
+            expected: expected.trim().to_string(),
+            actual,
+        }
+        .into());
+    }
+    tracing::info!("Checksum verified.");
+    Ok(())
+}
 
-    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(body))?;
+/// Extracts chromedriver (or msedgedriver) executable from zip `archive` and writes it to `out_path`.
+pub fn extract_chromedriver<R>(
+    archive: R,
+    out_path: &Path,
+) -> Result<(), Box<dyn Error + Send + Sync>>
+where
+    R: Read + Seek,
+{
+    let mut archive = zip::ZipArchive::new(archive)?;
     for i in 0..archive.len() {
         let mut file = archive.by_index(i)?;
         let file_name = file.mangled_name();
         if let Some(file_name) = file_name.file_name() {
             let file_name = file_name.to_string_lossy();
-            if file.name().ends_with("/")
-                || file_name != "chromedriver" && file_name != "chromedriver.exe"
-            {
+            let is_driver = ["chromedriver", "msedgedriver"]
+                .iter()
+                .any(|name| file_name == *name || file_name == format!("{name}.exe"));
+            if file.name().ends_with("/") || !is_driver {
                 continue;
             }
-            let mut out_file = std::fs::File::create(file_name.as_ref())?;
-            std::io::copy(&mut file, &mut out_file)?;
+            write_atomically(out_path, |out_file| {
+                std::io::copy(&mut file, out_file).map(|_| ())
+            })?;
+            return Ok(());
         }
     }
-    Ok(())
+    Err(ChromeDriverNotInArchive.into())
 }
 
+/// Archive doesn't contain chromedriver executable.
 #[derive(Debug)]
-struct ChromeDriverFetchError;
+pub struct ChromeDriverNotInArchive;
+
+impl Display for ChromeDriverNotInArchive {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Archive doesn't contain chromedriver executable.")
+    }
+}
+
+impl Error for ChromeDriverNotInArchive {}
+
+/// SHA-256 of the archive (or local executable) doesn't match the expected one.
+#[derive(Debug)]
+pub struct ChecksumMismatch {
+    pub expected: String,
+    pub actual: String,
+}
 
-impl Display for ChromeDriverFetchError {
+impl Display for ChecksumMismatch {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "No chromedriver version was found.")
+        write!(
+            f,
+            "Checksum mismatch, expected SHA-256 {} but got {}.",
+            self.expected, self.actual
+        )
     }
 }
 
-impl Error for ChromeDriverFetchError {}
+impl Error for ChecksumMismatch {}
@@ -0,0 +1,237 @@
+use std::{
+    error::Error,
+    fmt::Display,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::{
+    browser_flavor::BrowserFlavor, chrome_version::ChromeVersion,
+    get_chrome_version::get_chrome_version,
+};
+
+/// Browser executable found on the system together with its version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstalledBrowser {
+    pub path: PathBuf,
+    pub version: ChromeVersion,
+}
+
+/// Finds `browser` installed on the system.
+///
+/// `browser_path` (or environment variable named by [`BrowserFlavor::path_env`] when not set)
+/// is used as is, otherwise known executable names and install locations of stable, beta
+/// and dev channels are searched in that order.
+///
+/// Getting version of a browser fails if it doesn't finish in `timeout`.
+pub async fn find_browser(
+    browser: BrowserFlavor,
+    browser_path: Option<&Path>,
+    timeout: Duration,
+) -> Result<InstalledBrowser, Box<dyn Error + Send + Sync>> {
+    let os = std::env::consts::OS;
+    let explicit_path = browser_path
+        .map(Path::to_path_buf)
+        .or_else(|| std::env::var_os(browser.path_env()).map(PathBuf::from));
+    if let Some(path) = explicit_path {
+        let version = get_chrome_version(os, &path, timeout).await?;
+        return Ok(InstalledBrowser { path, version });
+    }
+
+    let candidates = browser_candidates(browser, os);
+    for path in candidates.iter().filter(|path| path.is_file()) {
+        match get_chrome_version(os, path, timeout).await {
+            Ok(version) => {
+                return Ok(InstalledBrowser {
+                    path: path.clone(),
+                    version,
+                })
+            }
+            Err(e) => tracing::warn!("Skipping {browser} '{}': {e}", path.display()),
+        }
+    }
+    Err(BrowserNotFound {
+        browser,
+        searched: candidates,
+    }
+    .into())
+}
+
+/// Known executables of `browser` on `os`, most preferred first.
+fn browser_candidates(browser: BrowserFlavor, os: &str) -> Vec<PathBuf> {
+    let locations = known_locations(browser, os);
+    match os {
+        // Plain names are searched in PATH
+        "linux" => {
+            let path_dirs = std::env::var_os("PATH")
+                .map(|path| std::env::split_paths(&path).collect::<Vec<_>>())
+                .unwrap_or_default();
+            locations
+                .iter()
+                .flat_map(|location| match location.starts_with('/') {
+                    true => vec![PathBuf::from(location)],
+                    false => path_dirs.iter().map(|dir| dir.join(location)).collect(),
+                })
+                .collect()
+        }
+        // App bundles are searched in system and user applications
+        "macos" => {
+            let app_dirs = std::iter::once(PathBuf::from("/Applications"))
+                .chain(dirs::home_dir().map(|home| home.join("Applications")))
+                .collect::<Vec<_>>();
+            locations
+                .iter()
+                .flat_map(|location| app_dirs.iter().map(move |dir| dir.join(location)))
+                .collect()
+        }
+        // Installations are searched in system and user program files
+        "windows" => {
+            let install_dirs = ["ProgramFiles", "ProgramFiles(x86)", "LOCALAPPDATA"]
+                .iter()
+                .filter_map(std::env::var_os)
+                .map(PathBuf::from)
+                .collect::<Vec<_>>();
+            locations
+                .iter()
+                .flat_map(|location| install_dirs.iter().map(move |dir| dir.join(location)))
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+fn known_locations(browser: BrowserFlavor, os: &str) -> &'static [&'static str] {
+    match (browser, os) {
+        (BrowserFlavor::Chrome | BrowserFlavor::ChromeHeadlessShell, "linux") => &[
+            "google-chrome",
+            "google-chrome-stable",
+            "google-chrome-beta",
+            "google-chrome-unstable",
+            "chromium",
+            "chromium-browser",
+            "/opt/google/chrome/chrome",
+            "/opt/google/chrome-beta/chrome",
+            "/opt/google/chrome-unstable/chrome",
+            "/snap/bin/chromium",
+            "/var/lib/flatpak/exports/bin/org.chromium.Chromium",
+        ],
+        (BrowserFlavor::Chrome | BrowserFlavor::ChromeHeadlessShell, "macos") => &[
+            "Google Chrome.app/Contents/MacOS/Google Chrome",
+            "Google Chrome Beta.app/Contents/MacOS/Google Chrome Beta",
+            "Google Chrome Dev.app/Contents/MacOS/Google Chrome Dev",
+            "Google Chrome Canary.app/Contents/MacOS/Google Chrome Canary",
+            "Chromium.app/Contents/MacOS/Chromium",
+        ],
+        (BrowserFlavor::Chrome | BrowserFlavor::ChromeHeadlessShell, "windows") => &[
+            r"Google\Chrome\Application\chrome.exe",
+            r"Google\Chrome Beta\Application\chrome.exe",
+            r"Google\Chrome Dev\Application\chrome.exe",
+            r"Google\Chrome SxS\Application\chrome.exe",
+            r"Chromium\Application\chrome.exe",
+        ],
+        (BrowserFlavor::Edge, "linux") => &[
+            "microsoft-edge",
+            "microsoft-edge-stable",
+            "microsoft-edge-beta",
+            "microsoft-edge-dev",
+            "/opt/microsoft/msedge/msedge",
+            "/opt/microsoft/msedge-beta/msedge",
+            "/opt/microsoft/msedge-dev/msedge",
+        ],
+        (BrowserFlavor::Edge, "macos") => &[
+            "Microsoft Edge.app/Contents/MacOS/Microsoft Edge",
+            "Microsoft Edge Beta.app/Contents/MacOS/Microsoft Edge Beta",
+            "Microsoft Edge Dev.app/Contents/MacOS/Microsoft Edge Dev",
+            "Microsoft Edge Canary.app/Contents/MacOS/Microsoft Edge Canary",
+        ],
+        (BrowserFlavor::Edge, "windows") => &[
+            r"Microsoft\Edge\Application\msedge.exe",
+            r"Microsoft\Edge Beta\Application\msedge.exe",
+            r"Microsoft\Edge Dev\Application\msedge.exe",
+            r"Microsoft\Edge SxS\Application\msedge.exe",
+        ],
+        (BrowserFlavor::Brave, "linux") => &[
+            "brave-browser",
+            "brave-browser-stable",
+            "brave-browser-beta",
+            "brave-browser-nightly",
+            "brave",
+            "/opt/brave.com/brave/brave",
+            "/opt/brave.com/brave-beta/brave",
+            "/opt/brave.com/brave-nightly/brave",
+            "/snap/bin/brave",
+            "/var/lib/flatpak/exports/bin/com.brave.Browser",
+        ],
+        (BrowserFlavor::Brave, "macos") => &[
+            "Brave Browser.app/Contents/MacOS/Brave Browser",
+            "Brave Browser Beta.app/Contents/MacOS/Brave Browser Beta",
+            "Brave Browser Nightly.app/Contents/MacOS/Brave Browser Nightly",
+        ],
+        (BrowserFlavor::Brave, "windows") => &[
+            r"BraveSoftware\Brave-Browser\Application\brave.exe",
+            r"BraveSoftware\Brave-Browser-Beta\Application\brave.exe",
+            r"BraveSoftware\Brave-Browser-Nightly\Application\brave.exe",
+        ],
+        (BrowserFlavor::Vivaldi, "linux") => &[
+            "vivaldi",
+            "vivaldi-stable",
+            "vivaldi-snapshot",
+            "/opt/vivaldi/vivaldi",
+            "/opt/vivaldi-snapshot/vivaldi-snapshot",
+            "/var/lib/flatpak/exports/bin/com.vivaldi.Vivaldi",
+        ],
+        (BrowserFlavor::Vivaldi, "macos") => &[
+            "Vivaldi.app/Contents/MacOS/Vivaldi",
+            "Vivaldi Snapshot.app/Contents/MacOS/Vivaldi Snapshot",
+        ],
+        (BrowserFlavor::Vivaldi, "windows") => &[
+            r"Vivaldi\Application\vivaldi.exe",
+            r"Vivaldi Snapshot\Application\vivaldi.exe",
+        ],
+        (BrowserFlavor::Opera, "linux") => &[
+            "opera",
+            "opera-beta",
+            "opera-developer",
+            "/usr/lib/x86_64-linux-gnu/opera/opera",
+            "/snap/bin/opera",
+            "/var/lib/flatpak/exports/bin/com.opera.Opera",
+        ],
+        (BrowserFlavor::Opera, "macos") => &[
+            "Opera.app/Contents/MacOS/Opera",
+            "Opera Beta.app/Contents/MacOS/Opera Beta",
+            "Opera Developer.app/Contents/MacOS/Opera Developer",
+        ],
+        (BrowserFlavor::Opera, "windows") => &[
+            r"Programs\Opera\opera.exe",
+            r"Programs\Opera beta\opera.exe",
+            r"Programs\Opera developer\opera.exe",
+            r"Opera\opera.exe",
+        ],
+        _ => &[],
+    }
+}
+
+/// No executable of the browser was found on the system.
+#[derive(Debug)]
+pub struct BrowserNotFound {
+    pub browser: BrowserFlavor,
+    pub searched: Vec<PathBuf>,
+}
+
+impl Display for BrowserNotFound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} not found, set {} to its executable. Searched: {}",
+            self.browser,
+            self.browser.path_env(),
+            self.searched
+                .iter()
+                .map(|path| format!("'{}'", path.display()))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
+
+impl Error for BrowserNotFound {}
@@ -1,28 +1,94 @@
-use std::process::Command;
-
-pub async fn get_chrome_version(os: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-    tracing::info!("Getting installed Chrome version...");
-    let command = match os {
-        "linux" => Command::new("google-chrome")
-            .arg("--version")
-            .output()?,
-        "macos" => Command::new("/Applications/Google Chrome.app/Contents/MacOS/Google Chrome")
-            .arg("--version")
-            .output()?,
-        "windows" => Command::new("powershell")
-            .arg("-c")
-            .arg("(Get-Item 'C:/Program Files/Google/Chrome/Application/chrome.exe').VersionInfo")
-            .output()?,
-        _ => panic!("Unsupported OS!"),
+use std::{error::Error, fmt::Display, path::Path, process::ExitStatus, time::Duration};
+
+use tokio::process::Command;
+
+use crate::{
+    chrome_version::{ChromeVersion, InvalidChromeVersion},
+    platform::UnsupportedPlatform,
+};
+
+pub async fn get_chrome_version(
+    os: &str,
+    chrome: &Path,
+    timeout: Duration,
+) -> Result<ChromeVersion, Box<dyn std::error::Error + Send + Sync>> {
+    tracing::info!("Getting version of Chrome '{}'...", chrome.display());
+    let mut command = match os {
+        "linux" | "macos" => {
+            let mut command = Command::new(chrome);
+            command.arg("--version");
+            command
+        }
+        "windows" => {
+            let mut command = Command::new("powershell");
+            command
+                .arg("-c")
+                .arg(format!("(Get-Item '{}').VersionInfo", chrome.display()));
+            command
+        }
+        _ => return Err(UnsupportedPlatform::current().into()),
     };
+    let command_line = format!("{:?}", command.as_std());
+    // Hanging browser is killed when the timeout elapses
+    command.kill_on_drop(true);
+    let command = tokio::time::timeout(timeout, command.output())
+        .await
+        .map_err(|_| VersionCommandTimeout {
+            command: command_line.clone(),
+            timeout,
+        })??;
+    if !command.status.success() {
+        return Err(VersionCommandFailed {
+            command: command_line,
+            status: command.status,
+            stderr: String::from_utf8_lossy(&command.stderr).trim().to_string(),
+        }
+        .into());
+    }
     let output = String::from_utf8(command.stdout)?;
 
-    let version = output
-        .lines()
-        .flat_map(|line| line.chars().filter(|&ch| ch.is_ascii_digit()))
-        .take(3)
-        .collect::<String>();
+    let version = ChromeVersion::find_in(&output)
+        .ok_or_else(|| InvalidChromeVersion(output.trim().to_string()))?;
 
-    tracing::info!("Currently installed Chrome version: {}", version);
+    tracing::info!("Chrome '{}' has version {}", chrome.display(), version);
     Ok(version)
 }
+
+/// Command getting Chrome (or chromedriver) version exited unsuccessfully.
+#[derive(Debug)]
+pub struct VersionCommandFailed {
+    pub command: String,
+    pub status: ExitStatus,
+    pub stderr: String,
+}
+
+impl Display for VersionCommandFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Command {} failed with {}: {}",
+            self.command, self.status, self.stderr
+        )
+    }
+}
+
+impl Error for VersionCommandFailed {}
+
+/// Command getting Chrome (or chromedriver) version didn't finish in time.
+#[derive(Debug)]
+pub struct VersionCommandTimeout {
+    pub command: String,
+    pub timeout: Duration,
+}
+
+impl Display for VersionCommandTimeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Command {} didn't finish in {:?}.",
+            self.command, self.timeout
+        )
+    }
+}
+
+impl Error for VersionCommandTimeout {}
@@ -0,0 +1,246 @@
+use std::{error::Error, fmt::Display, future::Future, time::Duration};
+
+use bytes::Bytes;
+
+/// HTTP client used for version lookups and downloads.
+///
+/// Requests failing with a transient error (5xx status, connection error, timeout,
+/// interrupted body) are retried with exponential backoff.
+///
+/// Default values are:
+/// ```compile_fail
+/// client: reqwest::Client::new(),
+/// retries: 3,
+/// retry_backoff: Duration::from_millis(500),
+/// ```
+#[derive(Debug, Clone)]
+pub struct HttpClient {
+    client: reqwest::Client,
+    retries: u32,
+    retry_backoff: Duration,
+}
+
+impl HttpClient {
+    /// Creates client wrapping custom `reqwest` client (e.g. with proxy or extra root certificates).
+    pub fn new(client: reqwest::Client) -> Self {
+        Self {
+            client,
+            retries: 3,
+            retry_backoff: Duration::from_millis(500),
+        }
+    }
+
+    /// Construct new builder of the underlying `reqwest` client.
+    pub fn builder() -> HttpClientBuilder {
+        HttpClientBuilder::default()
+    }
+
+    /// Sets how many times a failed request is retried.
+    pub fn set_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Sets delay before the first retry, it is doubled with every next retry.
+    pub fn set_retry_backoff(mut self, retry_backoff: Duration) -> Self {
+        self.retry_backoff = retry_backoff;
+        self
+    }
+
+    /// Underlying `reqwest` client, e.g. for requests that aren't retried.
+    pub fn client(&self) -> &reqwest::Client {
+        &self.client
+    }
+
+    /// Downloads body of `url`, checking response status and length.
+    pub async fn get_bytes(&self, url: &str) -> Result<Bytes, Box<dyn Error + Send + Sync>> {
+        self.with_retries(url, || async {
+            let mut resp = self.client.get(url).send().await?.error_for_status()?;
+            let content_length = resp.content_length();
+            let mut body = Vec::new();
+            loop {
+                match resp.chunk().await {
+                    Ok(Some(chunk)) => body.extend_from_slice(&chunk),
+                    Ok(None) => break,
+                    // Connection was closed before the announced length was received
+                    Err(_) if content_length.is_some_and(|len| (body.len() as u64) < len) => break,
+                    Err(e) => return Err(e.into()),
+                }
+            }
+            if let Some(expected) = content_length {
+                if body.len() as u64 != expected {
+                    return Err(IncompleteDownload {
+                        expected,
+                        actual: body.len() as u64,
+                    }
+                    .into());
+                }
+            }
+            Ok(Bytes::from(body))
+        })
+        .await
+    }
+
+    /// Downloads body of `url` as text, checking response status.
+    pub async fn get_text(&self, url: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
+        self.with_retries(url, || async {
+            let resp = self.client.get(url).send().await?.error_for_status()?;
+            Ok(resp.text().await?)
+        })
+        .await
+    }
+
+    async fn with_retries<T, F, Fut>(
+        &self,
+        url: &str,
+        request: F,
+    ) -> Result<T, Box<dyn Error + Send + Sync>>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T, Box<dyn Error + Send + Sync>>>,
+    {
+        let mut attempt = 0;
+        loop {
+            match request().await {
+                Ok(res) => return Ok(res),
+                Err(e) if attempt < self.retries && is_transient(e.as_ref()) => {
+                    let backoff = self.retry_backoff * 2u32.saturating_pow(attempt);
+                    attempt += 1;
+                    tracing::warn!(
+                        "Request to {url} failed: {e}, retrying in {backoff:?} ({attempt}/{})",
+                        self.retries
+                    );
+                    tokio::time::sleep(backoff).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl Default for HttpClient {
+    fn default() -> Self {
+        Self::new(reqwest::Client::new())
+    }
+}
+
+fn is_transient(e: &(dyn Error + Send + Sync + 'static)) -> bool {
+    if e.is::<IncompleteDownload>() {
+        return true;
+    }
+    let Some(e) = e.downcast_ref::<reqwest::Error>() else {
+        return false;
+    };
+    match e.status() {
+        Some(status) => status.is_server_error(),
+        None => e.is_connect() || e.is_timeout() || e.is_request() || e.is_body(),
+    }
+}
+
+/// Builder of [`HttpClient`] with proxy, timeouts and extra root certificates.
+///
+/// Default values are:
+/// ```compile_fail
+/// proxy: None,
+/// connect_timeout: Duration::from_secs(30),
+/// timeout: Duration::from_secs(300),
+/// root_certificates: vec![],
+/// retries: 3,
+/// retry_backoff: Duration::from_millis(500),
+/// ```
+#[derive(Debug)]
+pub struct HttpClientBuilder {
+    proxy: Option<reqwest::Proxy>,
+    connect_timeout: Duration,
+    timeout: Duration,
+    root_certificates: Vec<reqwest::Certificate>,
+    retries: u32,
+    retry_backoff: Duration,
+}
+
+impl HttpClientBuilder {
+    /// Sets proxy all requests go through.
+    pub fn set_proxy(mut self, proxy: Option<reqwest::Proxy>) -> Self {
+        self.proxy = proxy;
+        self
+    }
+
+    /// Sets timeout of connecting to the server (or proxy).
+    pub fn set_connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.connect_timeout = connect_timeout;
+        self
+    }
+
+    /// Sets timeout of a whole request, including download of the body.
+    pub fn set_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Adds trusted root certificate (e.g. of a corporate proxy).
+    pub fn add_root_certificate(mut self, certificate: reqwest::Certificate) -> Self {
+        self.root_certificates.push(certificate);
+        self
+    }
+
+    /// Sets how many times a failed request is retried.
+    pub fn set_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Sets delay before the first retry, it is doubled with every next retry.
+    pub fn set_retry_backoff(mut self, retry_backoff: Duration) -> Self {
+        self.retry_backoff = retry_backoff;
+        self
+    }
+
+    /// Builds the client, fails when the underlying `reqwest` client can't be created
+    /// (e.g. TLS backend can't be initialized).
+    pub fn build(self) -> Result<HttpClient, reqwest::Error> {
+        let mut builder = reqwest::Client::builder()
+            .connect_timeout(self.connect_timeout)
+            .timeout(self.timeout);
+        if let Some(proxy) = self.proxy {
+            builder = builder.proxy(proxy);
+        }
+        for certificate in self.root_certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
+        Ok(HttpClient::new(builder.build()?)
+            .set_retries(self.retries)
+            .set_retry_backoff(self.retry_backoff))
+    }
+}
+
+impl Default for HttpClientBuilder {
+    fn default() -> Self {
+        Self {
+            proxy: None,
+            connect_timeout: Duration::from_secs(30),
+            timeout: Duration::from_secs(300),
+            root_certificates: Vec::new(),
+            retries: 3,
+            retry_backoff: Duration::from_millis(500),
+        }
+    }
+}
+
+/// Downloaded body is shorter or longer than announced by the server.
+#[derive(Debug)]
+pub struct IncompleteDownload {
+    pub expected: u64,
+    pub actual: u64,
+}
+
+impl Display for IncompleteDownload {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Incomplete download, expected {} bytes but got {}.",
+            self.expected, self.actual
+        )
+    }
+}
+
+impl Error for IncompleteDownload {}
@@ -0,0 +1,68 @@
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    cache::write_atomically,
+    fetch_chromedriver::{extract_chromedriver, verify_sha256},
+};
+
+/// Chromedriver that is already available locally and is installed without network access.
+#[derive(Debug, Clone)]
+pub enum LocalDriver {
+    /// Zip archive containing chromedriver (e.g. `chromedriver-linux64.zip`).
+    Archive(PathBuf),
+    /// Chromedriver executable (e.g. `/usr/bin/chromedriver` from distro `chromium-driver`).
+    Binary(PathBuf),
+}
+
+/// Installs chromedriver from `local_driver` to `out_path`.
+///
+/// If `expected_sha256` is set, local archive (or executable) has to match it.
+pub fn install_chromedriver(
+    local_driver: &LocalDriver,
+    expected_sha256: Option<&str>,
+    out_path: &Path,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    match local_driver {
+        LocalDriver::Archive(archive_path) => {
+            tracing::info!(
+                "Installing chromedriver from archive '{}'",
+                archive_path.display()
+            );
+            let archive = std::fs::read(archive_path)?;
+            verify_sha256(&archive, expected_sha256)?;
+            extract_chromedriver(std::io::Cursor::new(archive), out_path)
+        }
+        LocalDriver::Binary(binary_path) => {
+            tracing::info!(
+                "Installing chromedriver from binary '{}'",
+                binary_path.display()
+            );
+            let binary = std::fs::read(binary_path)?;
+            verify_sha256(&binary, expected_sha256)?;
+            write_atomically(out_path, |out_file| {
+                std::io::Write::write_all(out_file, &binary)
+            })?;
+            Ok(())
+        }
+    }
+}
+
+/// Finds chromedriver installed on the system (e.g. by distro `chromium-driver` package).
+pub fn find_system_chromedriver() -> Option<PathBuf> {
+    let exe_name = format!("chromedriver{}", std::env::consts::EXE_SUFFIX);
+    std::env::var_os("PATH")
+        .iter()
+        .flat_map(std::env::split_paths)
+        .map(|dir| dir.join(&exe_name))
+        .chain(
+            [
+                "/usr/lib/chromium/chromedriver",
+                "/usr/lib/chromium-browser/chromedriver",
+            ]
+            .map(PathBuf::from),
+        )
+        .find(|path| path.is_file())
+}
@@ -1,22 +1,55 @@
+pub use browser_flavor::{BrowserFlavor, ChromiumMilestoneUnsupported, UnknownChromiumVersion};
+pub use cache::{CacheLock, DriverCache};
 pub use capabilities::DefaultCapabilitiesBuilder;
-use fetch_chromedriver::fetch_chromedriver;
-use patch_chromedriver::patch_chromedriver;
-use rand::Rng;
-use spawn_chromedriver::spawn_chromedriver;
-use std::{error::Error, fmt::Display, process::Child};
+pub use chrome_version::{ChromeVersion, InvalidChromeVersion};
+pub use chromedriver_process::ChromeDriverProcess;
+pub use driver_builder::ChromeDriverBuilder;
+pub use driver_source::{
+    BrowserDownloadUnsupported, ChromeForTestingSource, DriverSource, EdgeDriverSource,
+};
+pub use fetch_browser::PinnedChrome;
+pub use fetch_chromedriver::{ChecksumMismatch, ChromeDriverNotInArchive};
+pub use find_browser::{find_browser, BrowserNotFound, InstalledBrowser};
+pub use get_chrome_version::{VersionCommandFailed, VersionCommandTimeout};
+pub use http_client::{HttpClient, HttpClientBuilder, IncompleteDownload};
+pub use install_chromedriver::LocalDriver;
+pub use patch_chromedriver::{
+    patch_chromedriver, verify_patched, InvalidMarkerPattern, InvalidReplacementKey, MarkerPattern,
+    PatchConfig, PatchReport, PatchStrategy, PatchVerification, PatchedMarker, TokenReplacement,
+};
+pub use platform::{Platform, UnsupportedPlatform};
+pub use port::{NoFreePort, PortAllocation};
+pub use spawn_chromedriver::{
+    ChromeDriverExited, ChromeDriverNotReady, DriverVersionMismatch, LogConfig, LogLevel,
+    SessionNotCreated,
+};
+use std::{error::Error, fmt::Display};
 pub use thirtyfour;
 use thirtyfour::WebDriver;
+mod browser_flavor;
+mod cache;
 mod capabilities;
+mod chrome_version;
+mod chromedriver_process;
+mod driver_builder;
 mod driver_ext;
+mod driver_source;
+mod fetch_browser;
 mod fetch_chromedriver;
+mod find_browser;
 mod get_chrome_version;
+mod http_client;
+mod install_chromedriver;
 mod patch_chromedriver;
+mod platform;
+mod port;
 mod spawn_chromedriver;
 pub use driver_ext::Chrome;
 
 /// Fetches a new ChromeDriver executable and patches it to prevent detection.
 /// Returns a WebDriver instance (with default capabilities) and handle to chromedriver process.
-pub async fn chrome() -> Result<(WebDriver, Child), Box<dyn std::error::Error + Send + Sync>> {
+pub async fn chrome(
+) -> Result<(WebDriver, ChromeDriverProcess), Box<dyn std::error::Error + Send + Sync>> {
     chrome_with_capabilities(DefaultCapabilitiesBuilder::new().into_chrome_caps().into()).await
 }
 
@@ -25,22 +58,8 @@ pub async fn chrome() -> Result<(WebDriver, Child), Box<dyn std::error::Error +
 /// If chromedriver fails to start 3 times new chromedriver is redownloaded.
 pub async fn chrome_with_capabilities(
     capabilities: thirtyfour::Capabilities,
-) -> Result<(WebDriver, Child), Box<dyn std::error::Error + Send + Sync>> {
-    let res = try_start_chrome(capabilities.clone(), 3, false).await;
-    if res.is_ok() {
-        return res;
-    }
-    let os = std::env::consts::OS;
-    let chromedriver_executable = match os {
-        "linux" => "chromedriver",
-        "macos" => "chromedriver",
-        "windows" => "chromedriver.exe",
-        _ => return Err(UnsupportedOS.into()),
-    };
-    let _ = std::fs::remove_file(chromedriver_executable);
-    let chromedriver_executable = get_patched_chrome_driver_executable()?;
-    let _ = std::fs::remove_file(chromedriver_executable);
-    try_start_chrome(capabilities, 3, false).await
+) -> Result<(WebDriver, ChromeDriverProcess), Box<dyn std::error::Error + Send + Sync>> {
+    ChromeDriverBuilder::new().start(capabilities).await
 }
 
 /// Fetches a new ChromeDriver executable and patches it to prevent detection.
@@ -49,56 +68,12 @@ pub async fn try_start_chrome(
     capabilities: thirtyfour::Capabilities,
     num_attempts: u8,
     skip_chromedriver_check: bool,
-) -> Result<(WebDriver, Child), Box<dyn std::error::Error + Send + Sync>> {
-    let chromedriver_executable = get_patched_chrome_driver_executable()?;
-    if !skip_chromedriver_check {
-        if std::path::Path::new("chromedriver").exists()
-            || std::path::Path::new("chromedriver.exe").exists()
-        {
-            tracing::info!("ChromeDriver already exists!");
-        } else {
-            tracing::info!("ChromeDriver does not exist! Fetching...");
-            fetch_chromedriver().await?;
-        }
-        if std::path::Path::new(chromedriver_executable).exists() {
-            tracing::info!("Detected patched chromedriver executable!");
-        } else {
-            patch_chromedriver(chromedriver_executable)?;
-        }
-    }
-    tracing::info!("Starting chromedriver...");
-    let port: u16 = rand::rng().random_range(2000..5000);
-    let mut chrome_driver_handle = spawn_chromedriver(chromedriver_executable, port)?;
-    let mut driver = None;
-    let mut attempt: u8 = 0u8;
-    while driver.is_none() && attempt < num_attempts {
-        attempt += 1;
-        match WebDriver::new(&format!("http://127.0.0.1:{}", port), capabilities.clone()).await {
-            Ok(d) => driver = Some(d),
-            Err(e) => {
-                tracing::error!("Got error when initializing chrome: {e:#?}");
-                tokio::time::sleep(std::time::Duration::from_millis(250)).await
-            }
-        }
-    }
-    let driver = driver.ok_or_else(|| {
-        let _ = chrome_driver_handle.kill();
-        let _ = chrome_driver_handle.wait();
-        DriverCreationFailed
-    })?;
-    Ok((driver, chrome_driver_handle))
-}
-
-fn get_patched_chrome_driver_executable(
-) -> Result<&'static str, Box<dyn std::error::Error + Send + Sync>> {
-    let os = std::env::consts::OS;
-    let chromedriver_executable = match os {
-        "linux" => "chromedriver_PATCHED",
-        "macos" => "chromedriver_PATCHED",
-        "windows" => "chromedriver_PATCHED.exe",
-        _ => return Err(UnsupportedOS.into()),
-    };
-    Ok(chromedriver_executable)
+) -> Result<(WebDriver, ChromeDriverProcess), Box<dyn std::error::Error + Send + Sync>> {
+    ChromeDriverBuilder::new()
+        .set_num_attempts(num_attempts)
+        .set_skip_chromedriver_check(skip_chromedriver_check)
+        .try_start(capabilities)
+        .await
 }
 
 #[derive(Debug)]
@@ -110,13 +85,3 @@ impl Display for DriverCreationFailed {
     }
 }
 impl Error for DriverCreationFailed {}
-
-#[derive(Debug)]
-struct UnsupportedOS;
-
-impl Display for UnsupportedOS {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Your OS is not supported.")
-    }
-}
-impl Error for UnsupportedOS {}
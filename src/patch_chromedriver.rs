@@ -1,65 +1,451 @@
-use std::error::Error;
+use std::{error::Error, fmt::Display, io::Write, path::Path};
 
+use memchr::memmem;
 use rand::Rng;
+use sha2::{Digest, Sha256};
 
-pub fn patch_chromedriver(chromedriver_executable: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
-    tracing::info!("Starting ChromeDriver executable patch...");
-    let file_name = if cfg!(windows) {
-        "chromedriver.exe"
-    } else {
-        "chromedriver"
-    };
-    let f = std::fs::read(file_name)?;
-    let mut new_chromedriver_bytes = f.clone();
-    let mut total_cdc = String::from("");
-    let mut cdc_pos_list = Vec::new();
-    let mut is_cdc_present = false;
-    let mut patch_ct = 0;
-    for i in 0..f.len() - 3 {
-        if "cdc_"
-            == format!(
-                "{}{}{}{}",
-                f[i] as char,
-                f[i + 1] as char,
-                f[i + 2] as char,
-                f[i + 3] as char
-            )
-            .as_str()
-        {
-            for x in i + 4..i + 22 {
-                total_cdc.push_str(&(f[x] as char).to_string());
-            }
-            is_cdc_present = true;
-            cdc_pos_list.push(i);
-            total_cdc = String::from("");
+use crate::cache::write_atomically;
+
+/// Marker injected by chromedriver into pages (e.g. `cdc_adoQpoasnfa76pfcZLmcfl`).
+const CDC_PREFIX: &[u8] = b"cdc_";
+/// Number of bytes following the prefix that are replaced.
+const CDC_TOKEN_LEN: usize = 18;
+const LETTERS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+/// Start of the function body injected by chromedriver (e.g. `{window.cdc_adoQ..._Array=window.Array;...;}`).
+const CDC_BODY_START: &[u8] = b"{window.cdc_";
+const CDC_BODY_END: &[u8] = b";}";
+/// Maximum length of the injected function body.
+const CDC_BODY_MAX_LEN: usize = 4096;
+
+/// How chromedriver is patched to prevent detection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum PatchStrategy {
+    /// Replaces tokens of `cdc_` variables with random letters.
+    #[default]
+    Rename,
+    /// Rewrites the function body defining `cdc_` variables into a no-op of the same length,
+    /// as upstream undetected-chromedriver does. Renamed variables are still detectable by shape.
+    NeutralizeBody,
+}
+
+/// Marker searched for by [`PatchStrategy::Rename`], its token following the prefix is replaced.
+///
+/// Default pattern is the `cdc_` marker of chromedriver:
+/// ```compile_fail
+/// prefix: b"cdc_",
+/// token_len: 18,
+/// charset: b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ",
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MarkerPattern {
+    prefix: Vec<u8>,
+    token_len: usize,
+    charset: Vec<u8>,
+}
+
+impl MarkerPattern {
+    /// Construct new pattern replacing `token_len` bytes following `prefix` with ASCII letters.
+    pub fn new(prefix: impl Into<Vec<u8>>, token_len: usize) -> Self {
+        Self {
+            prefix: prefix.into(),
+            token_len,
+            charset: LETTERS.to_vec(),
         }
     }
-    if is_cdc_present {
-        tracing::info!("Found cdcs");
-    } else {
-        tracing::warn!("No cdcs were found!");
+
+    /// Sets characters replacement tokens are made of.
+    ///
+    /// [`verify_patched`] considers tokens made of these characters only as patched,
+    /// so the charset shouldn't contain characters of original tokens (e.g. digits).
+    pub fn set_charset(mut self, charset: impl Into<Vec<u8>>) -> Self {
+        self.charset = charset.into();
+        self
     }
-    let get_random_char = || -> char {
-        "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ"
-            .chars()
-            .collect::<Vec<char>>()[rand::rng().random_range(0..48)]
-    };
 
-    for i in cdc_pos_list {
-        for x in i + 4..i + 22 {
-            new_chromedriver_bytes[x] = get_random_char() as u8;
+    pub fn prefix(&self) -> &[u8] {
+        &self.prefix
+    }
+
+    pub fn token_len(&self) -> usize {
+        self.token_len
+    }
+
+    pub fn charset(&self) -> &[u8] {
+        &self.charset
+    }
+
+    /// Token following marker at `offset`, cut off by the end of `bytes`.
+    fn token<'a>(&self, bytes: &'a [u8], offset: usize) -> &'a [u8] {
+        let token_start = offset + self.prefix.len();
+        let token_end = (token_start + self.token_len).min(bytes.len());
+        &bytes[token_start..token_end]
+    }
+
+    fn is_patched_token(&self, token: &[u8]) -> bool {
+        !token.is_empty() && token.iter().all(|b| self.charset.contains(b))
+    }
+}
+
+impl Default for MarkerPattern {
+    fn default() -> Self {
+        Self::new(CDC_PREFIX, CDC_TOKEN_LEN)
+    }
+}
+
+/// How replacement tokens of `cdc_` variables are chosen by [`PatchStrategy::Rename`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub enum TokenReplacement {
+    /// Random letters, every patch produces a different executable.
+    #[default]
+    Random,
+    /// Letters derived from the seed, the same seed always produces the same executable.
+    Seed(u64),
+    /// Letters of the key, repeated to the length of a token.
+    Key(String),
+}
+
+/// Configuration of [`patch_chromedriver`].
+///
+/// Default values are:
+/// ```compile_fail
+/// strategy: PatchStrategy::Rename,
+/// replacement: TokenReplacement::Random,
+/// patterns: vec![MarkerPattern::default()],
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PatchConfig {
+    strategy: PatchStrategy,
+    replacement: TokenReplacement,
+    patterns: Vec<MarkerPattern>,
+}
+
+impl Default for PatchConfig {
+    fn default() -> Self {
+        Self {
+            strategy: PatchStrategy::default(),
+            replacement: TokenReplacement::default(),
+            patterns: vec![MarkerPattern::default()],
         }
-        patch_ct += 1;
     }
-    tracing::info!("Patched {} cdcs!", patch_ct);
+}
+
+impl PatchConfig {
+    /// Construct new config.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_strategy(mut self, strategy: PatchStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Sets how replacement tokens are chosen, use seed or key for reproducible executables.
+    pub fn set_replacement(mut self, replacement: TokenReplacement) -> Self {
+        self.replacement = replacement;
+        self
+    }
+
+    /// Sets markers replaced by [`PatchStrategy::Rename`] and checked by [`verify_patched`].
+    pub fn set_patterns(mut self, patterns: Vec<MarkerPattern>) -> Self {
+        self.patterns = patterns;
+        self
+    }
+
+    /// Adds marker to the default `cdc_` marker (or the ones already set).
+    pub fn add_pattern(mut self, pattern: MarkerPattern) -> Self {
+        self.patterns.push(pattern);
+        self
+    }
+
+    pub fn strategy(&self) -> PatchStrategy {
+        self.strategy
+    }
+
+    pub fn replacement(&self) -> &TokenReplacement {
+        &self.replacement
+    }
+
+    pub fn patterns(&self) -> &[MarkerPattern] {
+        &self.patterns
+    }
+
+    fn validate(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        for pattern in &self.patterns {
+            if pattern.prefix.is_empty() || pattern.charset.is_empty() {
+                return Err(InvalidMarkerPattern(pattern.clone()).into());
+            }
+        }
+        if let TokenReplacement::Key(key) = &self.replacement {
+            let in_charsets = |b: u8| self.patterns.iter().all(|p| p.charset.contains(&b));
+            if key.is_empty() || !key.bytes().all(in_charsets) {
+                return Err(InvalidReplacementKey(key.clone()).into());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Result of patching chromedriver.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatchReport {
+    pub strategy: PatchStrategy,
+    /// Patched markers (or function bodies) in order of their offsets.
+    pub markers: Vec<PatchedMarker>,
+    /// SHA-256 (hex encoded) of the original executable.
+    pub input_sha256: String,
+    /// SHA-256 (hex encoded) of the patched executable.
+    pub output_sha256: String,
+}
+
+/// Marker (or function body) replaced by [`patch_chromedriver`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatchedMarker {
+    /// Prefix of the matched pattern (or start of the function body).
+    pub pattern: String,
+    /// Offset of the marker prefix (or function body) in the executable.
+    pub offset: usize,
+    /// Token following the prefix (or function body) before patching.
+    pub original: String,
+    /// Token following the prefix (or function body) after patching.
+    pub replacement: String,
+}
+
+/// Markers found by [`verify_patched`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatchVerification {
+    /// Offsets of markers that are patched.
+    pub patched: Vec<usize>,
+    /// Offsets of markers that are still unpatched.
+    pub unpatched: Vec<usize>,
+}
+
+impl PatchVerification {
+    /// Returns whether no unpatched marker remains.
+    pub fn is_patched(&self) -> bool {
+        self.unpatched.is_empty()
+    }
+}
+
+/// Reads chromedriver from `chromedriver_executable`, patches it according to `config`
+/// and writes the result to `patched_chromedriver_executable`.
+///
+/// Length of the executable never changes.
+pub fn patch_chromedriver(
+    chromedriver_executable: &Path,
+    patched_chromedriver_executable: &Path,
+    config: &PatchConfig,
+) -> Result<PatchReport, Box<dyn Error + Send + Sync>> {
+    let strategy = config.strategy;
+    tracing::info!("Starting ChromeDriver executable patch ({strategy:?})...");
+    config.validate()?;
+    let mut chromedriver_bytes = std::fs::read(chromedriver_executable)?;
+    let input_sha256 = format!("{:x}", Sha256::digest(&chromedriver_bytes));
+    let markers = match strategy {
+        PatchStrategy::Rename => patch_markers(
+            &mut chromedriver_bytes,
+            &config.patterns,
+            &config.replacement,
+        ),
+        PatchStrategy::NeutralizeBody => neutralize_cdc_bodies(&mut chromedriver_bytes),
+    };
+    if markers.is_empty() {
+        tracing::warn!("No cdcs were found!");
+    } else {
+        tracing::info!("Patched {} cdcs!", markers.len());
+    }
 
     tracing::info!("Starting to write to binary file...");
-    let _file = std::fs::File::create(chromedriver_executable)?;
-    match std::fs::write(chromedriver_executable, new_chromedriver_bytes) {
+    // Written atomically, so a process running the patched executable never sees it half-written
+    match write_atomically(patched_chromedriver_executable, |file| {
+        file.write_all(&chromedriver_bytes)
+    }) {
         Ok(_res) => {
-            tracing::info!("Successfully wrote patched executable to 'chromedriver_PATCHED'!",)
+            tracing::info!(
+                "Successfully wrote patched executable to '{}'!",
+                patched_chromedriver_executable.display()
+            )
+        }
+        Err(err) => {
+            tracing::error!("Error when writing patch to file! Error: {}", err);
+            return Err(err.into());
         }
-        Err(err) => tracing::error!("Error when writing patch to file! Error: {}", err),
     };
-    Ok(())
+    Ok(PatchReport {
+        strategy,
+        markers,
+        input_sha256,
+        output_sha256: format!("{:x}", Sha256::digest(&chromedriver_bytes)),
+    })
 }
+
+/// Scans executable at `path` for markers of `config` and checks that all of them are patched.
+///
+/// With [`PatchStrategy::Rename`] a marker is considered patched when its token consists of
+/// characters of the pattern's charset only, as written by [`patch_chromedriver`].
+/// Token of original chromedriver (`adoQpoasnfa76pfcZLmcfl`) contains digits.
+///
+/// With [`PatchStrategy::NeutralizeBody`] offsets of remaining `{window.cdc_` function bodies
+/// are reported as unpatched, neutralized bodies can't be told apart from other code,
+/// so `patched` is always empty.
+pub fn verify_patched(
+    path: &Path,
+    config: &PatchConfig,
+) -> Result<PatchVerification, Box<dyn Error + Send + Sync>> {
+    config.validate()?;
+    let bytes = std::fs::read(path)?;
+    let mut verification = PatchVerification {
+        patched: Vec::new(),
+        unpatched: Vec::new(),
+    };
+    if config.strategy == PatchStrategy::NeutralizeBody {
+        verification.unpatched = memmem::find_iter(&bytes, CDC_BODY_START).collect();
+        return Ok(verification);
+    }
+    for pattern in &config.patterns {
+        for offset in memmem::find_iter(&bytes, &pattern.prefix) {
+            if pattern.is_patched_token(pattern.token(&bytes, offset)) {
+                verification.patched.push(offset);
+            } else {
+                verification.unpatched.push(offset);
+            }
+        }
+    }
+    verification.patched.sort_unstable();
+    verification.unpatched.sort_unstable();
+    Ok(verification)
+}
+
+/// Replaces tokens following every marker of `patterns` in `bytes` in place.
+///
+/// Tokens cut off by the end of `bytes` are replaced only up to the end.
+fn patch_markers(
+    bytes: &mut [u8],
+    patterns: &[MarkerPattern],
+    replacement: &TokenReplacement,
+) -> Vec<PatchedMarker> {
+    // Markers are found before patching, so replaced tokens never form new markers
+    let mut found = patterns
+        .iter()
+        .flat_map(|pattern| memmem::find_iter(bytes, &pattern.prefix).map(move |o| (o, pattern)))
+        .collect::<Vec<_>>();
+    found.sort_by_key(|(offset, _)| *offset);
+    let mut rng = rand::rng();
+    let mut markers = Vec::new();
+    let mut patched_end = 0;
+    for (offset, pattern) in found {
+        if offset < patched_end {
+            // Marker is a part of already replaced token
+            continue;
+        }
+        let token_start = offset + pattern.prefix.len();
+        let token_end = token_start + pattern.token(bytes, offset).len();
+        let original = String::from_utf8_lossy(&bytes[token_start..token_end]).into_owned();
+        let token = &mut bytes[token_start..token_end];
+        let charset = &pattern.charset;
+        match replacement {
+            TokenReplacement::Random => {
+                for b in token {
+                    *b = charset[rng.random_range(0..charset.len())];
+                }
+            }
+            TokenReplacement::Seed(seed) => seeded_token(*seed, markers.len(), charset, token),
+            TokenReplacement::Key(key) => {
+                for (b, k) in token.iter_mut().zip(key.bytes().cycle()) {
+                    *b = k;
+                }
+            }
+        }
+        markers.push(PatchedMarker {
+            pattern: String::from_utf8_lossy(&pattern.prefix).into_owned(),
+            offset,
+            original,
+            replacement: String::from_utf8_lossy(&bytes[token_start..token_end]).into_owned(),
+        });
+        patched_end = token_end;
+    }
+    markers
+}
+
+/// Fills `token` of marker number `index` with characters of `charset` derived from `seed`.
+///
+/// Characters come from SHA-256 of the seed and the index, so they don't depend on the `rand` version.
+fn seeded_token(seed: u64, index: usize, charset: &[u8], token: &mut [u8]) {
+    for (block, chunk) in token.chunks_mut(32).enumerate() {
+        let digest = Sha256::new()
+            .chain_update(seed.to_le_bytes())
+            .chain_update((index as u64).to_le_bytes())
+            .chain_update((block as u64).to_le_bytes())
+            .finalize();
+        for (b, d) in chunk.iter_mut().zip(digest) {
+            *b = charset[d as usize % charset.len()];
+        }
+    }
+}
+
+/// Replaces every function body defining `cdc_` variables in `bytes`
+/// with an empty block padded with spaces to the same length.
+fn neutralize_cdc_bodies(bytes: &mut [u8]) -> Vec<PatchedMarker> {
+    let start_finder = memmem::Finder::new(CDC_BODY_START);
+    let end_finder = memmem::Finder::new(CDC_BODY_END);
+    let mut bodies = Vec::new();
+    let mut pos = 0;
+    while let Some(found) = start_finder.find(&bytes[pos..]) {
+        let offset = pos + found;
+        let search_end = (offset + CDC_BODY_MAX_LEN).min(bytes.len());
+        let Some(body_len) = end_finder
+            .find(&bytes[offset..search_end])
+            .map(|end| end + CDC_BODY_END.len())
+            .filter(|&len| !bytes[offset..offset + len].contains(&b'\n'))
+        else {
+            tracing::warn!("End of function body at {offset} not found, skipping it.");
+            pos = offset + CDC_BODY_START.len();
+            continue;
+        };
+        let body = &mut bytes[offset..offset + body_len];
+        let original = String::from_utf8_lossy(body).into_owned();
+        body.fill(b' ');
+        body[0] = b'{';
+        body[body_len - 1] = b'}';
+        bodies.push(PatchedMarker {
+            pattern: String::from_utf8_lossy(CDC_BODY_START).into_owned(),
+            offset,
+            original,
+            replacement: String::from_utf8_lossy(body).into_owned(),
+        });
+        pos = offset + body_len;
+    }
+    bodies
+}
+
+/// Replacement key is empty or contains characters missing in a charset of the marker patterns.
+#[derive(Debug)]
+pub struct InvalidReplacementKey(pub String);
+
+impl Display for InvalidReplacementKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "\"{}\" is not a valid replacement key, only characters of marker charsets are allowed.",
+            self.0
+        )
+    }
+}
+
+impl Error for InvalidReplacementKey {}
+
+/// Marker pattern has empty prefix or charset.
+#[derive(Debug)]
+pub struct InvalidMarkerPattern(pub MarkerPattern);
+
+impl Display for InvalidMarkerPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Marker pattern with prefix \"{}\" is not valid, prefix and charset must not be empty.",
+            String::from_utf8_lossy(&self.0.prefix)
+        )
+    }
+}
+
+impl Error for InvalidMarkerPattern {}
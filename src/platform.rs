@@ -0,0 +1,92 @@
+use std::{error::Error, fmt::Display};
+
+/// Platform chromedriver is fetched for, derived from OS and CPU architecture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Platform {
+    Linux64,
+    /// There are no Chrome for Testing builds for this platform,
+    /// system chromedriver is used instead.
+    LinuxArm64,
+    MacX64,
+    MacArm64,
+    Win32,
+    Win64,
+}
+
+impl Platform {
+    /// Detects platform of the current machine.
+    pub fn current() -> Result<Self, UnsupportedPlatform> {
+        Self::from_os_arch(std::env::consts::OS, std::env::consts::ARCH)
+    }
+
+    /// Maps OS and architecture (as in [`std::env::consts`]) to a platform.
+    pub fn from_os_arch(os: &str, arch: &str) -> Result<Self, UnsupportedPlatform> {
+        let platform = match (os, arch) {
+            ("linux", "x86_64") => Self::Linux64,
+            ("linux", "aarch64") => Self::LinuxArm64,
+            ("macos", "x86_64") => Self::MacX64,
+            ("macos", "aarch64") => Self::MacArm64,
+            ("windows", "x86") => Self::Win32,
+            // Windows on ARM runs x64 binaries through emulation
+            ("windows", "x86_64" | "aarch64") => Self::Win64,
+            _ => {
+                return Err(UnsupportedPlatform {
+                    os: os.to_string(),
+                    arch: arch.to_string(),
+                })
+            }
+        };
+        Ok(platform)
+    }
+
+    /// Chrome for Testing platform identifier (e.g. `linux64` or `mac-arm64`).
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Linux64 => "linux64",
+            Self::LinuxArm64 => "linux-arm64",
+            Self::MacX64 => "mac-x64",
+            Self::MacArm64 => "mac-arm64",
+            Self::Win32 => "win32",
+            Self::Win64 => "win64",
+        }
+    }
+
+    /// Returns whether Chrome for Testing publishes builds for this platform.
+    pub fn has_chrome_for_testing(&self) -> bool {
+        !matches!(self, Self::LinuxArm64)
+    }
+}
+
+impl Display for Platform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// OS or CPU architecture is not supported.
+#[derive(Debug)]
+pub struct UnsupportedPlatform {
+    pub os: String,
+    pub arch: String,
+}
+
+impl UnsupportedPlatform {
+    pub(crate) fn current() -> Self {
+        Self {
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+        }
+    }
+}
+
+impl Display for UnsupportedPlatform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Your platform ({} on {}) is not supported.",
+            self.os, self.arch
+        )
+    }
+}
+
+impl Error for UnsupportedPlatform {}
@@ -0,0 +1,64 @@
+use std::{error::Error, fmt::Display, net::TcpListener, ops::RangeInclusive};
+
+use rand::Rng;
+
+/// How port chromedriver listens on is chosen.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub enum PortAllocation {
+    /// Free port assigned by the OS.
+    #[default]
+    Os,
+    /// Free port from the range, probed from a random port onwards.
+    Range(RangeInclusive<u16>),
+}
+
+impl PortAllocation {
+    /// Finds port that is free at the moment.
+    ///
+    /// Port is not reserved, so another process can take it before chromedriver binds it.
+    pub fn free_port(&self) -> Result<u16, NoFreePort> {
+        match self {
+            Self::Os => TcpListener::bind(("127.0.0.1", 0))
+                .and_then(|listener| listener.local_addr())
+                .map(|addr| addr.port())
+                .map_err(|_| NoFreePort(None)),
+            Self::Range(range) => {
+                if range.is_empty() {
+                    return Err(NoFreePort(Some(range.clone())));
+                }
+                let (start, end) = (*range.start() as u32, *range.end() as u32);
+                let len = end - start + 1;
+                let first = rand::rng().random_range(0..len);
+                (0..len)
+                    .map(|i| (start + (first + i) % len) as u16)
+                    .find(|&port| is_port_free(port))
+                    .ok_or_else(|| NoFreePort(Some(range.clone())))
+            }
+        }
+    }
+}
+
+/// Returns whether nothing listens on `port` of the loopback interface.
+pub(crate) fn is_port_free(port: u16) -> bool {
+    TcpListener::bind(("127.0.0.1", port)).is_ok()
+}
+
+/// No free port was found (in the range).
+#[derive(Debug)]
+pub struct NoFreePort(pub Option<RangeInclusive<u16>>);
+
+impl Display for NoFreePort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.0 {
+            Some(range) => write!(
+                f,
+                "No free port in range {}-{}.",
+                range.start(),
+                range.end()
+            ),
+            None => write!(f, "OS didn't assign a free port."),
+        }
+    }
+}
+
+impl Error for NoFreePort {}
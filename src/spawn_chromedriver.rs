@@ -1,14 +1,511 @@
 #[cfg(any(target_os = "linux", target_os = "macos"))]
 use std::os::unix::fs::PermissionsExt;
 use std::{
+    collections::VecDeque,
     error::Error,
-    process::{Child, Command, Stdio},
+    fmt::Display,
+    io::{BufRead, BufReader, Read},
+    path::{Path, PathBuf},
+    process::{Child, Command, ExitStatus, Stdio},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
 };
 
+use crate::{
+    chrome_version::{ChromeVersion, InvalidChromeVersion},
+    get_chrome_version::{VersionCommandFailed, VersionCommandTimeout},
+};
+
+/// How long a single `/status` request can take.
+const STATUS_REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+const STATUS_POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// How long output of exited chromedriver is waited for.
+const OUTPUT_CLOSE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Log level of chromedriver (`--log-level`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LogLevel {
+    All,
+    Debug,
+    Info,
+    Warning,
+    Severe,
+    Off,
+}
+
+impl LogLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::All => "ALL",
+            Self::Debug => "DEBUG",
+            Self::Info => "INFO",
+            Self::Warning => "WARNING",
+            Self::Severe => "SEVERE",
+            Self::Off => "OFF",
+        }
+    }
+}
+
+/// Logging configuration of chromedriver.
+///
+/// Default values are:
+/// ```compile_fail
+/// log_path: None,
+/// log_level: None,
+/// verbose: false,
+/// append_log: false,
+/// kept_lines: 100,
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LogConfig {
+    log_path: Option<PathBuf>,
+    log_level: Option<LogLevel>,
+    verbose: bool,
+    append_log: bool,
+    kept_lines: usize,
+}
+
+impl LogConfig {
+    /// Construct new config.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets file chromedriver writes its log to (`--log-path`) instead of stderr.
+    pub fn set_log_path(mut self, log_path: Option<PathBuf>) -> Self {
+        self.log_path = log_path;
+        self
+    }
+
+    /// Sets minimal level of logged messages (`--log-level`).
+    pub fn set_log_level(mut self, log_level: Option<LogLevel>) -> Self {
+        self.log_level = log_level;
+        self
+    }
+
+    /// When set, chromedriver logs verbosely (`--verbose`).
+    pub fn set_verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    /// When set, log file is appended to instead of being rewritten (`--append-log`).
+    pub fn set_append_log(mut self, append_log: bool) -> Self {
+        self.append_log = append_log;
+        self
+    }
+
+    /// Sets how many last lines of chromedriver output are kept for error reports.
+    pub fn set_kept_lines(mut self, kept_lines: usize) -> Self {
+        self.kept_lines = kept_lines;
+        self
+    }
+
+    /// Command line arguments of chromedriver.
+    fn args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(log_path) = &self.log_path {
+            args.push(format!("--log-path={}", log_path.display()));
+        }
+        if let Some(log_level) = self.log_level {
+            args.push(format!("--log-level={}", log_level.as_str()));
+        }
+        if self.verbose {
+            args.push("--verbose".to_string());
+        }
+        if self.append_log {
+            args.push("--append-log".to_string());
+        }
+        args
+    }
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            log_path: None,
+            log_level: None,
+            verbose: false,
+            append_log: false,
+            kept_lines: 100,
+        }
+    }
+}
+
+/// Last lines written by chromedriver to stdout and stderr.
+#[derive(Debug, Clone)]
+pub(crate) struct OutputLines {
+    lines: Arc<Mutex<VecDeque<String>>>,
+    capacity: usize,
+    /// Number of output streams that are still being read.
+    open_streams: Arc<AtomicUsize>,
+    /// Whether chromedriver reported it was started.
+    started: Arc<AtomicBool>,
+    /// Whether chromedriver prints nothing, not even that it was started (`--log-level=OFF`).
+    silent: bool,
+    /// Whether chromedriver reported its port was taken.
+    port_taken: Arc<AtomicBool>,
+}
+
+impl OutputLines {
+    fn new(capacity: usize, silent: bool) -> Self {
+        Self {
+            lines: Arc::default(),
+            capacity,
+            open_streams: Arc::new(AtomicUsize::new(0)),
+            started: Arc::default(),
+            silent,
+            port_taken: Arc::default(),
+        }
+    }
+
+    /// Records state reported by `line`, lines are scanned even when they aren't kept.
+    fn scan(&self, line: &str) {
+        if line.contains("started successfully") {
+            self.started.store(true, Ordering::Release);
+        }
+        let line = line.to_ascii_lowercase();
+        // e.g. `bind() failed: Address already in use (98)` and `Port not available. Exiting...`
+        if line.contains("address already in use") || line.contains("port not available") {
+            self.port_taken.store(true, Ordering::Release);
+        }
+    }
+
+    /// Returns whether chromedriver reported it was started
+    /// (`ChromeDriver was started successfully on port ...`).
+    /// Silent chromedriver is assumed to be started.
+    pub fn started(&self) -> bool {
+        self.silent || self.started.load(Ordering::Acquire)
+    }
+
+    /// Returns whether chromedriver reported its port was taken by another process.
+    pub fn port_taken(&self) -> bool {
+        self.port_taken.load(Ordering::Acquire)
+    }
+
+    fn push(&self, line: String) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut lines = self.lines.lock().unwrap_or_else(|e| e.into_inner());
+        if lines.len() == self.capacity {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+
+    /// Returns at most `n` last lines.
+    pub fn last_lines(&self, n: usize) -> Vec<String> {
+        let lines = self.lines.lock().unwrap_or_else(|e| e.into_inner());
+        lines
+            .iter()
+            .skip(lines.len().saturating_sub(n))
+            .cloned()
+            .collect()
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        self.last_lines(usize::MAX)
+    }
+
+    /// Error of chromedriver that exited with `status`.
+    ///
+    /// Output is read to the end first, unless processes started by chromedriver keep it open.
+    pub async fn exited(&self, status: ExitStatus) -> ChromeDriverExited {
+        let deadline = tokio::time::Instant::now() + OUTPUT_CLOSE_TIMEOUT;
+        while self.open_streams.load(Ordering::Acquire) > 0
+            && tokio::time::Instant::now() < deadline
+        {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        ChromeDriverExited {
+            status,
+            output: self.lines(),
+        }
+    }
+
+    /// Reads lines of `stream` on a new thread, logs them and keeps the last ones.
+    ///
+    /// Lines without a chromedriver log level are logged at `default_level`.
+    fn read(
+        &self,
+        name: &str,
+        stream: impl Read + Send + 'static,
+        default_level: tracing::Level,
+    ) -> std::io::Result<()> {
+        let output = self.clone();
+        output.open_streams.fetch_add(1, Ordering::AcqRel);
+        let res = std::thread::Builder::new()
+            .name(name.to_string())
+            .spawn(move || {
+                let mut reader = BufReader::new(stream);
+                let mut buf = Vec::new();
+                // Invalid UTF-8 is replaced instead of ending the thread
+                while matches!(reader.read_until(b'\n', &mut buf), Ok(n) if n > 0) {
+                    let line = String::from_utf8_lossy(&buf).trim_end().to_string();
+                    buf.clear();
+                    log_line(&line, default_level);
+                    output.scan(&line);
+                    output.push(line);
+                }
+                output.open_streams.fetch_sub(1, Ordering::AcqRel);
+            });
+        if res.is_err() {
+            self.open_streams.fetch_sub(1, Ordering::AcqRel);
+        }
+        res.map(|_| ())
+    }
+}
+
+/// Logs `line` of chromedriver output (e.g. `[1700000000.123][SEVERE]: bind() failed`)
+/// at the tracing level matching its chromedriver log level.
+fn log_line(line: &str, default_level: tracing::Level) {
+    let level = line
+        .strip_prefix('[')
+        .and_then(|rest| rest.split_once("]["))
+        .and_then(|(_, rest)| rest.split_once(']'))
+        .and_then(|(level, _)| match level {
+            "SEVERE" => Some(tracing::Level::ERROR),
+            "WARNING" => Some(tracing::Level::WARN),
+            "INFO" => Some(tracing::Level::INFO),
+            "DEBUG" => Some(tracing::Level::DEBUG),
+            "ALL" | "VERBOSE" => Some(tracing::Level::TRACE),
+            _ => None,
+        })
+        .unwrap_or(default_level);
+    match level {
+        tracing::Level::ERROR => tracing::error!("{}", line),
+        tracing::Level::WARN => tracing::warn!("{}", line),
+        tracing::Level::INFO => tracing::info!("{}", line),
+        tracing::Level::DEBUG => tracing::debug!("{}", line),
+        tracing::Level::TRACE => tracing::trace!("{}", line),
+    }
+}
+
 pub fn spawn_chromedriver(
-    chromedriver_executable: &str,
+    chromedriver_executable: &Path,
+    port: u16,
+    log_config: &LogConfig,
+) -> Result<(Child, OutputLines), Box<dyn Error + Send + Sync>> {
+    let chromedriver_executable = std::path::absolute(chromedriver_executable)?;
+    ensure_executable(&chromedriver_executable)?;
+    let mut command = Command::new(&chromedriver_executable);
+    // Own process group lets ChromeDriverProcess kill browsers started by chromedriver too
+    #[cfg(target_os = "linux")]
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+    let mut chrome_driver_handle = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .arg(format!("--port={}", port))
+        .args(log_config.args())
+        .spawn()?;
+    let chrome_driver_stdout = chrome_driver_handle
+        .stdout
+        .take()
+        .expect("Chromedriver process has no stdout.");
+    let chrome_driver_stderr = chrome_driver_handle
+        .stderr
+        .take()
+        .expect("Chromedriver process has no stderr.");
+    let output = OutputLines::new(
+        log_config.kept_lines,
+        log_config.log_level == Some(LogLevel::Off),
+    );
+    let log_threads = output
+        .read(
+            "ChromeDriverThread",
+            chrome_driver_stdout,
+            tracing::Level::INFO,
+        )
+        .and_then(|_| {
+            output.read(
+                "ChromeDriverStderrThread",
+                chrome_driver_stderr,
+                tracing::Level::WARN,
+            )
+        });
+    if let Err(e) = log_threads {
+        let _ = chrome_driver_handle.kill();
+        let _ = chrome_driver_handle.wait();
+        return Err(e.into());
+    }
+    Ok((chrome_driver_handle, output))
+}
+
+/// Polls `/status` of chromedriver listening on `port` until it reports it is ready.
+///
+/// Status is trusted only after chromedriver reported it was started on its own port,
+/// otherwise it could come from another process listening on `port`.
+/// With logging off chromedriver reports nothing, then another process is detected
+/// only by chromedriver exiting.
+/// Fails when chromedriver exits or isn't ready before `timeout` elapses.
+pub(crate) async fn wait_until_ready(
+    chrome_driver_handle: &mut Child,
+    output: &OutputLines,
     port: u16,
-) -> Result<Child, Box<dyn Error + Send + Sync>> {
+    timeout: Duration,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    // Proxy from environment must not be used for local chromedriver
+    let client = reqwest::Client::builder()
+        .no_proxy()
+        .timeout(STATUS_REQUEST_TIMEOUT)
+        .build()?;
+    let url = format!("http://127.0.0.1:{port}/status");
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        if let Some(status) = chrome_driver_handle.try_wait()? {
+            return Err(output.exited(status).await.into());
+        }
+        if output.started() && !output.port_taken() && is_ready(&client, &url).await {
+            tracing::info!("Chromedriver is ready on port {port}");
+            return Ok(());
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return Err(ChromeDriverNotReady {
+                port,
+                timeout,
+                output: output.lines(),
+            }
+            .into());
+        }
+        tokio::time::sleep(STATUS_POLL_INTERVAL).await;
+    }
+}
+
+/// Returns whether `/status` at `url` reports `{"value": {"ready": true}}`.
+async fn is_ready(client: &reqwest::Client, url: &str) -> bool {
+    let Ok(response) = client.get(url).send().await else {
+        return false;
+    };
+    let Ok(body) = response.bytes().await else {
+        return false;
+    };
+    serde_json::from_slice::<serde_json::Value>(&body)
+        .is_ok_and(|status| status["value"]["ready"].as_bool() == Some(true))
+}
+
+/// Runs `chromedriver --version` and parses version from its output
+/// (e.g. `ChromeDriver 131.0.6778.85 (...)`).
+pub async fn chromedriver_version(
+    chromedriver_executable: &Path,
+    timeout: Duration,
+) -> Result<ChromeVersion, Box<dyn Error + Send + Sync>> {
+    let chromedriver_executable = std::path::absolute(chromedriver_executable)?;
+    ensure_executable(&chromedriver_executable)?;
+    let mut command = tokio::process::Command::new(&chromedriver_executable);
+    command.arg("--version");
+    let command_line = format!("{:?}", command.as_std());
+    // Hanging chromedriver is killed when the timeout elapses
+    command.kill_on_drop(true);
+    let output = tokio::time::timeout(timeout, command.output())
+        .await
+        .map_err(|_| VersionCommandTimeout {
+            command: command_line.clone(),
+            timeout,
+        })??;
+    if !output.status.success() {
+        return Err(VersionCommandFailed {
+            command: command_line,
+            status: output.status,
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        }
+        .into());
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let version = ChromeVersion::find_in(&stdout)
+        .ok_or_else(|| InvalidChromeVersion(stdout.trim().to_string()))?;
+    Ok(version)
+}
+
+/// Milestone of chromedriver installed from a local file doesn't match Chrome.
+#[derive(Debug)]
+pub struct DriverVersionMismatch {
+    pub driver_version: ChromeVersion,
+    pub chrome_version: ChromeVersion,
+}
+
+impl Display for DriverVersionMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Chromedriver {} doesn't match Chrome {}.",
+            self.driver_version, self.chrome_version
+        )
+    }
+}
+
+impl Error for DriverVersionMismatch {}
+
+/// Chromedriver exited before a session was created.
+#[derive(Debug)]
+pub struct ChromeDriverExited {
+    pub status: ExitStatus,
+    /// Last lines written by chromedriver.
+    pub output: Vec<String>,
+}
+
+impl Display for ChromeDriverExited {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Chromedriver exited with {}.", self.status)?;
+        for line in &self.output {
+            write!(f, "\n{line}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ChromeDriverExited {}
+
+/// Chromedriver didn't report it is ready in time.
+#[derive(Debug)]
+pub struct ChromeDriverNotReady {
+    pub port: u16,
+    pub timeout: Duration,
+    /// Last lines written by chromedriver.
+    pub output: Vec<String>,
+}
+
+impl Display for ChromeDriverNotReady {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Chromedriver on port {} wasn't ready in {:?}.",
+            self.port, self.timeout
+        )?;
+        for line in &self.output {
+            write!(f, "\n{line}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ChromeDriverNotReady {}
+
+/// Session couldn't be created with running chromedriver.
+#[derive(Debug)]
+pub struct SessionNotCreated {
+    /// Error of the last attempt.
+    pub error: String,
+    /// Last lines written by chromedriver.
+    pub output: Vec<String>,
+}
+
+impl Display for SessionNotCreated {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Session couldn't be created: {}", self.error)?;
+        for line in &self.output {
+            write!(f, "\n{line}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for SessionNotCreated {}
+
+fn ensure_executable(chromedriver_executable: &Path) -> std::io::Result<()> {
     #[cfg(any(target_os = "linux", target_os = "macos"))]
     {
         let mut perms = std::fs::metadata(chromedriver_executable)?.permissions();
@@ -16,27 +513,14 @@ pub fn spawn_chromedriver(
         if perms_oct & 0o500 != 0o500 || perms_oct & 0o050 != 0o050 || perms_oct & 0o005 != 0o005 {
             perms.set_mode(0o755);
             if let Err(e) = std::fs::set_permissions(chromedriver_executable, perms) {
-                tracing::error!("Can't set permission for \"{chromedriver_executable}\", got error: {e:?}");
+                tracing::error!(
+                    "Can't set permission for \"{}\", got error: {e:?}",
+                    chromedriver_executable.display()
+                );
             }
         }
     }
-    let mut chrome_driver_handle = Command::new(format!("./{}", chromedriver_executable))
-        .stdout(Stdio::piped())
-        .arg(format!("--port={}", port))
-        .spawn()
-        .expect("Failed to start chromedriver!");
-    let chrome_driver_stdout = chrome_driver_handle
-        .stdout
-        .take()
-        .expect("Chromedriver process has no stdout.");
-    std::thread::Builder::new()
-        .name("ChromeDriverThread".to_string())
-        .spawn(|| {
-            use std::io::BufRead;
-            let lines = std::io::BufReader::new(chrome_driver_stdout).lines();
-            for line in lines {
-                tracing::info!("{}", line.unwrap());
-            }
-        })?;
-    Ok(chrome_driver_handle)
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    let _ = chromedriver_executable;
+    Ok(())
 }
@@ -0,0 +1,54 @@
+#[cfg(test)]
+mod tests {
+    use undetected_chromedriver::{
+        BrowserFlavor, ChromeDriverBuilder, ChromeVersion, ChromiumMilestoneUnsupported,
+    };
+
+    #[test]
+    fn test_chromium_version() {
+        let chrome = ChromeVersion::new(131, 0, 6778, 85);
+        assert_eq!(
+            BrowserFlavor::Chrome.chromium_version(&chrome),
+            Some(chrome)
+        );
+        assert_eq!(
+            BrowserFlavor::Brave.chromium_version(&ChromeVersion::new(131, 1, 73, 97)),
+            Some(ChromeVersion::new(131, 0, 0, 0))
+        );
+        assert_eq!(
+            BrowserFlavor::Vivaldi.chromium_version(&ChromeVersion::new(7, 0, 3495, 29)),
+            Some(ChromeVersion::new(130, 0, 0, 0))
+        );
+        assert_eq!(
+            BrowserFlavor::Opera.chromium_version(&ChromeVersion::new(114, 0, 5282, 102)),
+            Some(ChromeVersion::new(128, 0, 0, 0))
+        );
+        assert_eq!(
+            BrowserFlavor::Opera.chromium_version(&ChromeVersion::new(116, 0, 5366, 21)),
+            Some(ChromeVersion::new(131, 0, 0, 0))
+        );
+    }
+
+    #[test]
+    fn test_unknown_chromium_version() {
+        assert_eq!(
+            BrowserFlavor::Vivaldi.chromium_version(&ChromeVersion::new(99, 0, 0, 0)),
+            None
+        );
+        assert_eq!(
+            BrowserFlavor::Opera.chromium_version(&ChromeVersion::new(12, 16, 0, 0)),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_edge_chromium_milestone_rejected() {
+        let err = ChromeDriverBuilder::new()
+            .set_browser_flavor(BrowserFlavor::Edge)
+            .set_chromium_milestone(Some(131))
+            .install()
+            .await
+            .unwrap_err();
+        assert!(err.is::<ChromiumMilestoneUnsupported>());
+    }
+}
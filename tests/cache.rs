@@ -0,0 +1,62 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::TestDir;
+    use std::time::Duration;
+    use undetected_chromedriver::{BrowserFlavor, ChromeVersion, DriverCache, Platform};
+
+    #[test]
+    fn test_driver_paths_per_driver() {
+        let cache = DriverCache::new("/cache");
+        let version = ChromeVersion::new(131, 0, 0, 0);
+        let paths = |browser| {
+            [
+                cache.driver_path(browser, &version, Platform::Linux64),
+                cache.patched_driver_path(browser, &version, Platform::Linux64),
+                cache.patch_metadata_path(browser, &version, Platform::Linux64),
+            ]
+        };
+        assert_eq!(
+            paths(BrowserFlavor::Edge),
+            [
+                "/cache/131.0.0.0/linux64/msedgedriver",
+                "/cache/131.0.0.0/linux64/msedgedriver_PATCHED",
+                "/cache/131.0.0.0/linux64/msedgedriver_PATCHED.json",
+            ]
+            .map(std::path::PathBuf::from)
+        );
+        // Browsers based on Chromium share chromedriver of the milestone
+        assert_eq!(paths(BrowserFlavor::Brave), paths(BrowserFlavor::Chrome));
+        assert_eq!(
+            paths(BrowserFlavor::Brave)[0],
+            std::path::Path::new("/cache/131.0.0.0/linux64/chromedriver")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_lock_waits_for_release() {
+        let dir = TestDir::new("cache-lock");
+        let cache = DriverCache::new(dir.path());
+        let version = ChromeVersion::new(131, 0, 6778, 85);
+
+        let lock = cache.lock(&version, Platform::Linux64).await.unwrap();
+        let waiting = {
+            let cache = cache.clone();
+            tokio::spawn(async move { cache.lock(&version, Platform::Linux64).await })
+        };
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(!waiting.is_finished());
+
+        // Other platforms are locked separately
+        let other = cache.lock(&version, Platform::Win64).await.unwrap();
+        drop(other);
+
+        drop(lock);
+        tokio::time::timeout(Duration::from_secs(5), waiting)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+    }
+}
@@ -0,0 +1,46 @@
+#[cfg(test)]
+mod tests {
+    use undetected_chromedriver::ChromeVersion;
+
+    #[test]
+    fn test_parse() {
+        let version: ChromeVersion = "131.0.6778.85".parse().unwrap();
+        assert_eq!(version, ChromeVersion::new(131, 0, 6778, 85));
+        assert_eq!(version.to_string(), "131.0.6778.85");
+        assert_eq!(
+            "1000.2".parse::<ChromeVersion>().unwrap(),
+            ChromeVersion::new(1000, 2, 0, 0)
+        );
+        assert!("131.0.6778.85.1".parse::<ChromeVersion>().is_err());
+        assert!("131.x".parse::<ChromeVersion>().is_err());
+        assert!("".parse::<ChromeVersion>().is_err());
+    }
+
+    #[test]
+    fn test_find_in_output() {
+        assert_eq!(
+            ChromeVersion::find_in("Google Chrome 131.0.6778.85 \n"),
+            Some(ChromeVersion::new(131, 0, 6778, 85))
+        );
+        assert_eq!(
+            ChromeVersion::find_in(
+                "\r\nProductVersion   FileVersion      FileName\r\n--------------   -----------      --------\r\n131.0.6778.86    131.0.6778.86    C:\\Program Files\\Google\\Chrome\\Application\\chrome.exe\r\n"
+            ),
+            Some(ChromeVersion::new(131, 0, 6778, 86))
+        );
+        assert_eq!(ChromeVersion::find_in("command not found"), None);
+    }
+
+    #[test]
+    fn test_ordering() {
+        let v99: ChromeVersion = "99.0.4844.51".parse().unwrap();
+        let v114: ChromeVersion = "114.0.5735.90".parse().unwrap();
+        let v1000: ChromeVersion = "1000.0.0.0".parse().unwrap();
+        assert!(v99 < v114);
+        assert!(v114 < v1000);
+        assert!(
+            "131.0.6778.9".parse::<ChromeVersion>().unwrap()
+                < "131.0.6778.85".parse::<ChromeVersion>().unwrap()
+        );
+    }
+}
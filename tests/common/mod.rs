@@ -0,0 +1,178 @@
+#![allow(dead_code)]
+
+use std::{
+    io::{Read, Write},
+    net::TcpListener,
+    path::{Path, PathBuf},
+};
+
+use undetected_chromedriver::{
+    BrowserFlavor, ChromeDriverBuilder, ChromeVersion, DriverSource, HttpClient, Platform,
+};
+
+/// Temporary directory of a test, removed when dropped.
+pub struct TestDir(PathBuf);
+
+impl TestDir {
+    /// Creates empty `undetected-chromedriver-test-<name>-<pid>` directory in system temp directory.
+    pub fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!(
+            "undetected-chromedriver-test-{name}-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    pub fn join(&self, path: impl AsRef<Path>) -> PathBuf {
+        self.0.join(path)
+    }
+
+    /// Writes `bytes` to file `name` and returns its path.
+    pub fn write(&self, name: impl AsRef<Path>, bytes: &[u8]) -> PathBuf {
+        let path = self.join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    /// Writes executable shell `script` to file `name` and returns its path.
+    #[cfg(unix)]
+    pub fn write_script(&self, name: impl AsRef<Path>, script: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+        let path = self.write(name, format!("#!/bin/sh\n{script}\n").as_bytes());
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Cache paths of chromedriver for the fake Chrome of [`fake_chrome_builder`].
+pub struct CachedDriver {
+    pub driver: PathBuf,
+    pub patched: PathBuf,
+    pub patch_metadata: PathBuf,
+}
+
+/// Builder for a fake Chrome 131.0.6778.85 written to `dir`, caching drivers in `dir`
+/// and not retrying failed requests, with paths its chromedriver is cached at.
+#[cfg(unix)]
+pub fn fake_chrome_builder(dir: &TestDir) -> (ChromeDriverBuilder, CachedDriver) {
+    let chrome = dir.write_script("chrome", "echo 'Google Chrome 131.0.6778.85'");
+    let builder = ChromeDriverBuilder::new()
+        .set_cache_dir(dir.join("cache"))
+        .set_browser_path(Some(chrome))
+        .set_http_client(HttpClient::builder().set_retries(0).build().unwrap());
+    let version = ChromeVersion::new(131, 0, 6778, 85);
+    let platform = Platform::current().unwrap();
+    let cache = builder.cache();
+    let driver = CachedDriver {
+        driver: cache.driver_path(BrowserFlavor::Chrome, &version, platform),
+        patched: cache.patched_driver_path(BrowserFlavor::Chrome, &version, platform),
+        patch_metadata: cache.patch_metadata_path(BrowserFlavor::Chrome, &version, platform),
+    };
+    (builder, driver)
+}
+
+/// Serves raw HTTP responses of `respond` (called with the request path)
+/// and returns base URL of the server.
+pub fn serve_raw(respond: impl Fn(&str) -> Vec<u8> + Send + 'static) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut buf = [0u8; 4096];
+            let len = stream.read(&mut buf).unwrap();
+            let request = String::from_utf8_lossy(&buf[..len]);
+            let path = request.split_whitespace().nth(1).unwrap_or_default();
+            let _ = stream.write_all(&respond(path));
+        }
+    });
+    format!("http://{addr}")
+}
+
+/// Serves `files` (path, body) over HTTP, other paths are not found.
+/// Returns base URL of the server.
+pub fn serve_files(files: Vec<(&'static str, Vec<u8>)>) -> String {
+    serve_raw(move |path| {
+        files
+            .iter()
+            .find(|(file, _)| *file == path)
+            .map_or_else(not_found, |(_, body)| http_response(200, body))
+    })
+}
+
+/// Raw HTTP response with `status` and `body`.
+pub fn http_response(status: u16, body: &[u8]) -> Vec<u8> {
+    let mut response = format!(
+        "HTTP/1.1 {status} X\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    )
+    .into_bytes();
+    response.extend_from_slice(body);
+    response
+}
+
+/// Raw HTTP response with 404 status.
+pub fn not_found() -> Vec<u8> {
+    http_response(404, b"not found")
+}
+
+/// Zip archive of executable `files` (name, content).
+pub fn zip_archive(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default().unix_permissions(0o755);
+    for (name, content) in files {
+        zip.start_file(*name, options).unwrap();
+        zip.write_all(content).unwrap();
+    }
+    zip.finish().unwrap().into_inner()
+}
+
+/// Source serving `chromedriver.zip`, `<browser>.zip` and `milestone-<milestone>` (its latest version) from `base` URL.
+#[derive(Debug)]
+pub struct TestSource(pub String);
+
+#[async_trait::async_trait]
+impl DriverSource for TestSource {
+    async fn driver_url(
+        &self,
+        _client: &HttpClient,
+        _chrome_version: &ChromeVersion,
+        _platform: Platform,
+    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(format!("{}/chromedriver.zip", self.0))
+    }
+
+    async fn milestone_version(
+        &self,
+        client: &HttpClient,
+        milestone: u32,
+    ) -> Result<ChromeVersion, Box<dyn std::error::Error + Send + Sync>> {
+        let body = client
+            .get_bytes(&format!("{}/milestone-{milestone}", self.0))
+            .await?;
+        Ok(String::from_utf8(body.to_vec())?.parse()?)
+    }
+
+    async fn browser_url(
+        &self,
+        _client: &HttpClient,
+        browser: BrowserFlavor,
+        _chrome_version: &ChromeVersion,
+        _platform: Platform,
+    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(format!("{}/{}.zip", self.0, browser.as_str()))
+    }
+}
@@ -0,0 +1,232 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{http_response, not_found, serve_files, serve_raw};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+    use std::time::Duration;
+    use undetected_chromedriver::{
+        BrowserFlavor, ChromeForTestingSource, ChromeVersion, DriverSource, EdgeDriverSource,
+        HttpClient, Platform,
+    };
+
+    /// Serves [`MILESTONES`] to every request except the first one, which fails with 503.
+    fn serve_failing_once() -> String {
+        let requests = AtomicUsize::new(0);
+        serve_raw(move |_| {
+            if requests.fetch_add(1, Ordering::SeqCst) == 0 {
+                http_response(503, b"error")
+            } else {
+                http_response(200, MILESTONES.as_bytes())
+            }
+        })
+    }
+
+    fn source(base: &str) -> ChromeForTestingSource {
+        ChromeForTestingSource::new()
+            .set_known_good_versions_url(format!("{base}/known-good.json"))
+            .set_milestone_versions_url(format!("{base}/milestones.json"))
+            .set_download_url_template("https://mirror.local/{version}/chromedriver-{platform}.zip")
+    }
+
+    const KNOWN_GOOD: &str = r#"{"versions":[{"version":"131.0.6778.69","downloads":{"chromedriver":[{"platform":"linux64","url":""}]}},{"version":"131.0.6778.70","downloads":{}}]}"#;
+    const MILESTONES: &str = r#"{"milestones":{"131":{"version":"131.0.6778.85"}}}"#;
+
+    #[tokio::test]
+    async fn test_exact_version() {
+        let base = serve_files(vec![
+            ("/known-good.json", KNOWN_GOOD.into()),
+            ("/milestones.json", MILESTONES.into()),
+        ]);
+        let url = source(&base)
+            .driver_url(
+                &HttpClient::default(),
+                &"131.0.6778.69".parse().unwrap(),
+                Platform::Linux64,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            url,
+            "https://mirror.local/131.0.6778.69/chromedriver-linux64.zip"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_milestone_fallback() {
+        let base = serve_files(vec![
+            ("/known-good.json", KNOWN_GOOD.into()),
+            ("/milestones.json", MILESTONES.into()),
+        ]);
+        let source = source(&base);
+        let client = HttpClient::default();
+        // Known good version without chromedriver download
+        let url = source
+            .driver_url(
+                &client,
+                &ChromeVersion::new(131, 0, 6778, 70),
+                Platform::Linux64,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            url,
+            "https://mirror.local/131.0.6778.85/chromedriver-linux64.zip"
+        );
+        // Unknown version
+        let url = source
+            .driver_url(
+                &client,
+                &ChromeVersion::new(131, 0, 6778, 99),
+                Platform::Linux64,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            url,
+            "https://mirror.local/131.0.6778.85/chromedriver-linux64.zip"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_milestone_only() {
+        let known_good_requests = Arc::new(AtomicUsize::new(0));
+        let requests = known_good_requests.clone();
+        let base = serve_raw(move |path| match path {
+            "/known-good.json" => {
+                requests.fetch_add(1, Ordering::SeqCst);
+                http_response(200, KNOWN_GOOD.as_bytes())
+            }
+            "/milestones.json" => http_response(200, MILESTONES.as_bytes()),
+            _ => not_found(),
+        });
+        // Version of a browser based on Chromium 131 (or of set_chromium_milestone)
+        let version = ChromeVersion::new(131, 0, 0, 0);
+        assert!(version.is_milestone_only());
+        let url = source(&base)
+            .driver_url(&HttpClient::default(), &version, Platform::Linux64)
+            .await
+            .unwrap();
+        assert_eq!(
+            url,
+            "https://mirror.local/131.0.6778.85/chromedriver-linux64.zip"
+        );
+        assert_eq!(known_good_requests.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_error_status() {
+        let base = serve_raw(|path| match path {
+            "/known-good.json" => http_response(500, b"error"),
+            "/milestones.json" => http_response(503, b"error"),
+            _ => not_found(),
+        });
+        let res = source(&base)
+            .driver_url(
+                &HttpClient::default().set_retry_backoff(Duration::from_millis(10)),
+                &ChromeVersion::new(131, 0, 6778, 69),
+                Platform::Linux64,
+            )
+            .await;
+        assert!(res.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_retry_transient_error() {
+        // First request fails with 503, the retried one succeeds
+        let base = serve_failing_once();
+        let client = HttpClient::default().set_retry_backoff(Duration::from_millis(10));
+        let version = source(&base).milestone_version(&client, 131).await.unwrap();
+        assert_eq!(version, ChromeVersion::new(131, 0, 6778, 85));
+
+        // Without retries the error is returned
+        let base = serve_failing_once();
+        let client = client.set_retries(0);
+        assert!(source(&base).milestone_version(&client, 131).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_custom_legacy_endpoints() {
+        let base = serve_files(vec![("/LATEST_RELEASE_113", b"113.0.5672.63\n".to_vec())]);
+        let source = ChromeForTestingSource::new()
+            .set_legacy_release_url_template(format!("{base}/LATEST_RELEASE_{{version}}"))
+            .set_legacy_download_url_template(
+                "https://mirror.local/{version}/chromedriver_{platform}.zip",
+            );
+        let url = source
+            .driver_url(
+                &HttpClient::default(),
+                &ChromeVersion::new(113, 0, 5672, 126),
+                Platform::Win64,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            url,
+            "https://mirror.local/113.0.5672.63/chromedriver_win32.zip"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pinned_browser() {
+        let base = serve_files(vec![("/milestones.json", MILESTONES.into())]);
+        let source = source(&base)
+            .set_browser_download_url_template(
+                "https://mirror.local/{version}/chrome-{platform}.zip",
+            )
+            .set_headless_shell_download_url_template(
+                "https://mirror.local/{version}/chrome-headless-shell-{platform}.zip",
+            );
+        let client = HttpClient::default();
+        let version = source.milestone_version(&client, 131).await.unwrap();
+        assert_eq!(version, ChromeVersion::new(131, 0, 6778, 85));
+        let url = source
+            .browser_url(&client, BrowserFlavor::Chrome, &version, Platform::MacArm64)
+            .await
+            .unwrap();
+        assert_eq!(
+            url,
+            "https://mirror.local/131.0.6778.85/chrome-mac-arm64.zip"
+        );
+        let url = source
+            .browser_url(
+                &client,
+                BrowserFlavor::ChromeHeadlessShell,
+                &version,
+                Platform::Linux64,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            url,
+            "https://mirror.local/131.0.6778.85/chrome-headless-shell-linux64.zip"
+        );
+        assert!(source.milestone_version(&client, 1).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_edge_driver() {
+        let source = EdgeDriverSource::new();
+        let client = HttpClient::default();
+        let version = ChromeVersion::new(131, 0, 2903, 86);
+        let url = source
+            .driver_url(&client, &version, Platform::MacArm64)
+            .await
+            .unwrap();
+        assert_eq!(
+            url,
+            "https://msedgedriver.microsoft.com/131.0.2903.86/edgedriver_mac64_m1.zip"
+        );
+        assert!(source
+            .driver_url(&client, &version, Platform::LinuxArm64)
+            .await
+            .is_err());
+        assert!(source
+            .browser_url(&client, BrowserFlavor::Edge, &version, Platform::Win64)
+            .await
+            .is_err());
+    }
+}
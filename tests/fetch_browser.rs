@@ -0,0 +1,104 @@
+mod common;
+
+#[cfg(test)]
+#[cfg(target_os = "linux")]
+mod tests {
+    use crate::common::{serve_files, zip_archive, TestDir, TestSource};
+    use undetected_chromedriver::{
+        BrowserFlavor, ChromeDriverBuilder, ChromeVersion, HttpClient, PinnedChrome, Platform,
+    };
+
+    #[tokio::test]
+    async fn test_fetch_pinned_chrome() {
+        let dir = TestDir::new("fetch-pinned-chrome");
+        let base = serve_files(vec![
+            (
+                "/chromedriver.zip",
+                zip_archive(&[("chromedriver-linux64/chromedriver", b"#!/bin/sh\n")]),
+            ),
+            (
+                "/chrome.zip",
+                zip_archive(&[
+                    ("chrome-linux64/chrome", b"#!/bin/sh\n"),
+                    ("chrome-linux64/locales/en-US.pak", b"pak"),
+                ]),
+            ),
+        ]);
+        let version = ChromeVersion::new(131, 0, 6778, 85);
+        let builder = ChromeDriverBuilder::new()
+            .set_cache_dir(dir.join("cache"))
+            .set_driver_source(TestSource(base))
+            .set_pinned_chrome(Some(PinnedChrome::Version(version)));
+        let browser_dir =
+            builder
+                .cache()
+                .browser_dir(BrowserFlavor::Chrome, &version, Platform::Linux64);
+        // Directory left by an incomplete extraction is replaced
+        let stale = dir.write(browser_dir.join("chrome-linux64/stale"), b"");
+
+        builder.install().await.unwrap();
+        assert!(browser_dir.join("chrome-linux64/chrome").exists());
+        assert_eq!(
+            std::fs::read(browser_dir.join("chrome-linux64/locales/en-US.pak")).unwrap(),
+            b"pak"
+        );
+        assert!(!stale.exists());
+        // No temporary directory is left next to the browser
+        let leftovers = std::fs::read_dir(browser_dir.parent().unwrap())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| name.starts_with("chrome."))
+            .collect::<Vec<_>>();
+        assert!(leftovers.is_empty(), "{leftovers:?}");
+    }
+
+    #[tokio::test]
+    async fn test_pinned_milestone_offline() {
+        let dir = TestDir::new("pinned-milestone-offline");
+        let base = serve_files(vec![
+            ("/milestone-131", b"131.0.6778.85".to_vec()),
+            (
+                "/chromedriver.zip",
+                zip_archive(&[(
+                    "chromedriver-linux64/chromedriver",
+                    b"#!/bin/sh\necho 'ChromeDriver 131.0.6778.85 (4a8f7b2e0c1d)'\n",
+                )]),
+            ),
+            (
+                "/chrome.zip",
+                zip_archive(&[("chrome-linux64/chrome", b"#!/bin/sh\n")]),
+            ),
+        ]);
+        let builder = ChromeDriverBuilder::new()
+            .set_cache_dir(dir.join("cache"))
+            .set_http_client(HttpClient::builder().set_retries(0).build().unwrap())
+            .set_pinned_chrome(Some(PinnedChrome::Milestone(131)));
+        builder
+            .clone()
+            .set_driver_source(TestSource(base))
+            .install()
+            .await
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(builder.cache().milestone_version_path(131)).unwrap(),
+            "131.0.6778.85"
+        );
+
+        // Milestone can't be resolved, the recorded version is used with the cached browser
+        let offline = serve_files(Vec::new());
+        builder
+            .clone()
+            .set_driver_source(TestSource(offline.clone()))
+            .install()
+            .await
+            .unwrap();
+
+        // Nothing recorded for another milestone
+        assert!(builder
+            .set_driver_source(TestSource(offline))
+            .set_pinned_chrome(Some(PinnedChrome::Milestone(130)))
+            .install()
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,82 @@
+mod common;
+
+#[cfg(test)]
+#[cfg(unix)]
+mod tests {
+    use crate::common::{
+        fake_chrome_builder, serve_files, serve_raw, zip_archive, TestDir, TestSource,
+    };
+    use sha2::{Digest, Sha256};
+    use std::path::Path;
+    use undetected_chromedriver::{ChecksumMismatch, ChromeDriverNotInArchive, IncompleteDownload};
+
+    /// Asserts that nothing but the lock file was left in the directory of `driver`.
+    fn assert_nothing_written(driver: &Path) {
+        let files = std::fs::read_dir(driver.parent().unwrap())
+            .map(|entries| {
+                entries
+                    .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+                    .filter(|name| !name.ends_with(".lock"))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        assert!(files.is_empty(), "{files:?}");
+    }
+
+    #[tokio::test]
+    async fn test_checksum_mismatch() {
+        let dir = TestDir::new("fetch-checksum-mismatch");
+        let archive = zip_archive(&[("chromedriver-linux64/chromedriver", b"#!/bin/sh\n")]);
+        let actual = format!("{:x}", Sha256::digest(&archive));
+        let (builder, cached) = fake_chrome_builder(&dir);
+        let builder = builder.set_driver_source(TestSource(serve_files(vec![(
+            "/chromedriver.zip",
+            archive,
+        )])));
+        let err = builder
+            .set_archive_sha256(Some("0".repeat(64)))
+            .install()
+            .await
+            .unwrap_err();
+        let mismatch = err.downcast_ref::<ChecksumMismatch>().unwrap();
+        assert_eq!(mismatch.expected, "0".repeat(64));
+        assert_eq!(mismatch.actual, actual);
+        assert_nothing_written(&cached.driver);
+    }
+
+    #[tokio::test]
+    async fn test_chromedriver_not_in_archive() {
+        let dir = TestDir::new("fetch-not-in-archive");
+        let archive = zip_archive(&[
+            ("chrome-linux64/chrome", b"#!/bin/sh\n"),
+            ("chromedriver-linux64/chromedriver.txt", b"readme"),
+        ]);
+        let (builder, cached) = fake_chrome_builder(&dir);
+        let builder = builder.set_driver_source(TestSource(serve_files(vec![(
+            "/chromedriver.zip",
+            archive,
+        )])));
+        let err = builder.install().await.unwrap_err();
+        assert!(err.is::<ChromeDriverNotInArchive>());
+        assert_nothing_written(&cached.driver);
+    }
+
+    #[tokio::test]
+    async fn test_incomplete_download() {
+        let dir = TestDir::new("fetch-incomplete-download");
+        // Connection is closed before the announced length is sent
+        let base = serve_raw(|_| {
+            b"HTTP/1.1 200 OK\r\nContent-Length: 1000\r\nConnection: close\r\n\r\nPK\x03\x04"
+                .to_vec()
+        });
+        let (builder, cached) = fake_chrome_builder(&dir);
+        let builder = builder.set_driver_source(TestSource(base));
+        let err = builder.install().await.unwrap_err();
+        let incomplete = err
+            .downcast_ref::<IncompleteDownload>()
+            .unwrap_or_else(|| panic!("{err:?}"));
+        assert_eq!(incomplete.expected, 1000);
+        assert_eq!(incomplete.actual, 4);
+        assert_nothing_written(&cached.driver);
+    }
+}
@@ -0,0 +1,69 @@
+mod common;
+
+#[cfg(test)]
+#[cfg(unix)]
+mod tests {
+    use crate::common::TestDir;
+    use std::time::Duration;
+    use undetected_chromedriver::{
+        find_browser, BrowserFlavor, ChromeVersion, VersionCommandFailed, VersionCommandTimeout,
+    };
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    #[tokio::test]
+    async fn test_explicit_chrome_path() {
+        let dir = TestDir::new("find-browser-chromium");
+        let chrome = dir.write_script("chromium", "echo 'Chromium 131.0.6778.85 snap'");
+        let installed = find_browser(BrowserFlavor::Chrome, Some(&chrome), TIMEOUT)
+            .await
+            .unwrap();
+        assert_eq!(installed.path, chrome);
+        assert_eq!(installed.version, ChromeVersion::new(131, 0, 6778, 85));
+
+        assert!(find_browser(
+            BrowserFlavor::Chrome,
+            Some(&chrome.with_file_name("missing")),
+            TIMEOUT
+        )
+        .await
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_explicit_edge_path() {
+        let dir = TestDir::new("find-browser-msedge");
+        let edge = dir.write_script("msedge", "echo 'Microsoft Edge 131.0.2903.86'");
+        let installed = find_browser(BrowserFlavor::Edge, Some(&edge), TIMEOUT)
+            .await
+            .unwrap();
+        assert_eq!(installed.version, ChromeVersion::new(131, 0, 2903, 86));
+    }
+
+    #[tokio::test]
+    async fn test_version_command_failed() {
+        let dir = TestDir::new("find-browser-broken-chrome");
+        let chrome = dir.write_script("broken-chrome", "echo 'missing libnss3.so' >&2\nexit 127");
+        let err = find_browser(BrowserFlavor::Chrome, Some(&chrome), TIMEOUT)
+            .await
+            .unwrap_err();
+        let err = err.downcast_ref::<VersionCommandFailed>().unwrap();
+        assert_eq!(err.status.code(), Some(127));
+        assert_eq!(err.stderr, "missing libnss3.so");
+        assert!(err.command.contains("broken-chrome"));
+    }
+
+    #[tokio::test]
+    async fn test_version_command_timeout() {
+        let dir = TestDir::new("find-browser-hanging-chrome");
+        let chrome = dir.write_script("hanging-chrome", "sleep 30");
+        let err = find_browser(
+            BrowserFlavor::Chrome,
+            Some(&chrome),
+            Duration::from_millis(200),
+        )
+        .await
+        .unwrap_err();
+        assert!(err.is::<VersionCommandTimeout>());
+    }
+}
@@ -2,7 +2,10 @@
 mod tests {
     use thirtyfour::prelude::ElementQueryable;
     use thirtyfour::By;
-    use undetected_chromedriver::{chrome, chrome_with_capabilities, DefaultCapabilitiesBuilder};
+    use undetected_chromedriver::{
+        chrome, chrome_with_capabilities, BrowserFlavor, ChromeDriverBuilder,
+        DefaultCapabilitiesBuilder,
+    };
 
     #[tokio::test]
     async fn test_headless_detection() {
@@ -26,7 +29,8 @@ mod tests {
             DefaultCapabilitiesBuilder::new()
                 .set_user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0.0.0 Safari/537.36")
                 .set_headless(true)
-                .into_chrome_caps(),
+                .into_chrome_caps()
+                .into(),
         )
         .await
         .unwrap();
@@ -42,4 +46,30 @@ mod tests {
         driver.quit().await.unwrap();
         handle.kill().unwrap();
     }
+
+    #[tokio::test]
+    async fn test_headless_shell_detection() {
+        let (driver, mut handle) = ChromeDriverBuilder::new()
+            .set_browser_flavor(BrowserFlavor::ChromeHeadlessShell)
+            .start(
+                DefaultCapabilitiesBuilder::new()
+                    .set_user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0.0.0 Safari/537.36")
+                    .set_browser_flavor(BrowserFlavor::ChromeHeadlessShell)
+                    .into_chrome_caps()
+                    .into(),
+            )
+            .await
+            .unwrap();
+        driver
+            .goto("https://arh.antoinevastel.com/bots/areyouheadless")
+            .await
+            .unwrap();
+        let is_headless = driver.query(By::XPath(r#"//*[@id="res"]/p"#));
+        assert_eq!(
+            is_headless.first().await.unwrap().text().await.unwrap(),
+            "You are not Chrome headless"
+        );
+        driver.quit().await.unwrap();
+        handle.kill().unwrap();
+    }
 }
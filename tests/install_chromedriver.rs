@@ -0,0 +1,179 @@
+mod common;
+
+#[cfg(test)]
+#[cfg(unix)]
+mod tests {
+    use crate::common::{fake_chrome_builder, zip_archive, TestDir};
+    use sha2::{Digest, Sha256};
+    use std::time::Duration;
+    use undetected_chromedriver::{
+        ChecksumMismatch, ChromeDriverBuilder, ChromeVersion, DriverVersionMismatch, LocalDriver,
+        PatchConfig, TokenReplacement, VersionCommandTimeout,
+    };
+
+    const DRIVER_SCRIPT: &str = "echo 'ChromeDriver 131.0.6778.85 (4a8f7b2e0c1d)'";
+
+    fn sha256(bytes: &[u8]) -> String {
+        format!("{:x}", Sha256::digest(bytes))
+    }
+
+    #[tokio::test]
+    async fn test_install_local_archive() {
+        let dir = TestDir::new("install-local-archive");
+        let driver_bytes = format!("#!/bin/sh\n{DRIVER_SCRIPT}\n").into_bytes();
+        let archive = zip_archive(&[
+            ("chromedriver-linux64/LICENSE.chromedriver", b"license"),
+            ("chromedriver-linux64/chromedriver", &driver_bytes),
+        ]);
+        let archive_path = dir.write("chromedriver-linux64.zip", &archive);
+        let (builder, cached) = fake_chrome_builder(&dir);
+        let builder = builder.set_local_driver(Some(LocalDriver::Archive(archive_path)));
+
+        let err = builder
+            .clone()
+            .set_archive_sha256(Some(sha256(b"other")))
+            .install()
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<ChecksumMismatch>().unwrap().actual,
+            sha256(&archive)
+        );
+        assert!(!cached.driver.exists());
+
+        let patched = builder
+            .set_archive_sha256(Some(sha256(&archive).to_uppercase()))
+            .install()
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&cached.driver).unwrap(), driver_bytes);
+        assert!(patched.exists());
+    }
+
+    #[tokio::test]
+    async fn test_install_local_binary() {
+        let dir = TestDir::new("install-local-binary");
+        let binary = dir.write_script("chromedriver", DRIVER_SCRIPT);
+        let (builder, cached) = fake_chrome_builder(&dir);
+        let builder = builder.set_local_driver(Some(LocalDriver::Binary(binary.clone())));
+
+        let err = builder
+            .clone()
+            .set_archive_sha256(Some(sha256(b"other")))
+            .install()
+            .await
+            .unwrap_err();
+        assert!(err.is::<ChecksumMismatch>());
+        assert!(!cached.driver.exists());
+
+        let binary_sha256 = sha256(&std::fs::read(&binary).unwrap());
+        let patched = builder
+            .set_archive_sha256(Some(binary_sha256))
+            .install()
+            .await
+            .unwrap();
+        assert_eq!(
+            std::fs::read(&cached.driver).unwrap(),
+            std::fs::read(&binary).unwrap()
+        );
+        assert!(patched.exists());
+    }
+
+    #[tokio::test]
+    async fn test_patch_metadata() {
+        let dir = TestDir::new("install-patch-metadata");
+        // Marker makes every patch with random tokens produce a different executable
+        let binary = dir.write_script(
+            "chromedriver",
+            &format!("{DRIVER_SCRIPT}\n# window.cdc_adoQpoasnfa76pfcZLmcfl_Array"),
+        );
+        let (builder, cached) = fake_chrome_builder(&dir);
+        let builder = builder.set_local_driver(Some(LocalDriver::Binary(binary)));
+        let metadata_path = &cached.patch_metadata;
+        let install = |builder: &ChromeDriverBuilder| {
+            let builder = builder.clone();
+            async move { std::fs::read(builder.install().await.unwrap()).unwrap() }
+        };
+        let metadata = || {
+            serde_json::from_slice::<serde_json::Value>(&std::fs::read(metadata_path).unwrap())
+                .unwrap()
+        };
+
+        let patched = install(&builder).await;
+        assert_eq!(
+            metadata()["patch_config"],
+            serde_json::json!({
+                "strategy": "rename",
+                "replacement": { "kind": "random" },
+                "patterns": [{
+                    "prefix": "6364635f",
+                    "token_len": 18,
+                    "charset": "6162636465666768696a6b6c6d6e6f707172737475767778797a4142434445464748494a4b4c4d4e4f505152535455565758595a",
+                }],
+            })
+        );
+        // Unchanged metadata, patched executable is reused
+        assert_eq!(install(&builder).await, patched);
+
+        // Missing metadata
+        std::fs::remove_file(metadata_path).unwrap();
+        let repatched = install(&builder).await;
+        assert_ne!(repatched, patched);
+        assert!(metadata_path.exists());
+
+        // Changed original executable
+        let mut original = std::fs::read(&cached.driver).unwrap();
+        original.extend_from_slice(b"# updated\n");
+        std::fs::write(&cached.driver, &original).unwrap();
+        let patched = install(&builder).await;
+        assert_ne!(patched, repatched);
+        assert_eq!(metadata()["source_sha256"], sha256(&original));
+
+        // Changed patch config
+        let seeded = builder
+            .clone()
+            .set_patch_config(PatchConfig::new().set_replacement(TokenReplacement::Seed(7)));
+        assert_ne!(install(&seeded).await, patched);
+        assert_eq!(
+            metadata()["patch_config"]["replacement"],
+            serde_json::json!({ "kind": "seed", "seed": 7 })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_local_driver_version_mismatch() {
+        let dir = TestDir::new("install-version-mismatch");
+        let local = dir.write_script(
+            "chromedriver",
+            "echo 'ChromeDriver 130.0.6723.116 (6ac35f94ae3d01152cf1946c896b0678e48f8ec4)'",
+        );
+        let (builder, cached) = fake_chrome_builder(&dir);
+        let builder = builder.set_local_driver(Some(LocalDriver::Binary(local)));
+        let err = builder.install().await.unwrap_err();
+        let mismatch = err.downcast_ref::<DriverVersionMismatch>().unwrap();
+        assert_eq!(
+            mismatch.driver_version,
+            ChromeVersion::new(130, 0, 6723, 116)
+        );
+        assert_eq!(
+            mismatch.chrome_version,
+            ChromeVersion::new(131, 0, 6778, 85)
+        );
+        assert!(!cached.driver.exists());
+    }
+
+    #[tokio::test]
+    async fn test_local_driver_version_timeout() {
+        let dir = TestDir::new("install-version-timeout");
+        let local = dir.write_script("chromedriver", "sleep 30");
+        let (builder, cached) = fake_chrome_builder(&dir);
+        let builder = builder.set_local_driver(Some(LocalDriver::Binary(local)));
+        let err = builder
+            .set_version_timeout(Duration::from_millis(200))
+            .install()
+            .await
+            .unwrap_err();
+        assert!(err.is::<VersionCommandTimeout>());
+        assert!(!cached.driver.exists());
+    }
+}
@@ -0,0 +1,182 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::TestDir;
+    use undetected_chromedriver::{
+        patch_chromedriver, verify_patched, MarkerPattern, PatchConfig, PatchReport, PatchStrategy,
+        TokenReplacement,
+    };
+
+    const DRIVER: &[u8] =
+        b"\x00\x01window.cdc_adoQpoasnfa76pfcZLmcfl_Array;\x00cdc_adoQpoasnfa76pfcZLmcfl_Symbol";
+
+    /// Patches `bytes` written to a test file and returns the report and the patched bytes.
+    fn patch(name: &str, bytes: &[u8]) -> (PatchReport, Vec<u8>) {
+        patch_with(name, bytes, &PatchConfig::new())
+    }
+
+    fn patch_with(name: &str, bytes: &[u8], config: &PatchConfig) -> (PatchReport, Vec<u8>) {
+        let dir = TestDir::new(&format!("patch-{name}"));
+        let driver = dir.write(name, bytes);
+        let patched = driver.with_file_name(format!("{name}_PATCHED"));
+        let report = patch_chromedriver(&driver, &patched, config).unwrap();
+        (report, std::fs::read(&patched).unwrap())
+    }
+
+    #[test]
+    fn test_patch_markers() {
+        let (report, patched) = patch("markers", DRIVER);
+        assert_eq!(patched.len(), DRIVER.len());
+        assert!(!patched.windows(18).any(|w| w == b"adoQpoasnfa76pfcZL"));
+        // Prefix and surrounding bytes are kept
+        assert_eq!(&patched[..13], b"\x00\x01window.cdc_");
+        assert_eq!(&patched[31..41], b"mcfl_Array");
+        assert!(patched[13..31].iter().all(u8::is_ascii_alphabetic));
+
+        let offsets = report.markers.iter().map(|m| m.offset).collect::<Vec<_>>();
+        assert_eq!(offsets, [9, 43]);
+        for marker in &report.markers {
+            assert_eq!(marker.pattern, "cdc_");
+            assert_eq!(marker.original, "adoQpoasnfa76pfcZL");
+            let start = marker.offset + 4;
+            assert_eq!(marker.replacement.as_bytes(), &patched[start..start + 18]);
+        }
+        assert_eq!(
+            report.input_sha256,
+            "d2cf1dfe8d2c20ce2ebdc81393a6bb0ebfc4a0dc21c72858d5449222b85cd7e1"
+        );
+        assert_ne!(report.input_sha256, report.output_sha256);
+    }
+
+    #[test]
+    fn test_patch_marker_at_end() {
+        let (report, patched) = patch("end", b"\x00\x00cdc_adoQ");
+        assert_eq!(&patched[..6], b"\x00\x00cdc_");
+        assert_eq!(report.markers[0].original, "adoQ");
+        assert_eq!(report.markers[0].replacement.as_bytes(), &patched[6..]);
+        assert_eq!(patch("prefix-only", b"cdc_").1, b"cdc_");
+    }
+
+    #[test]
+    fn test_patch_tiny_files() {
+        assert_eq!(patch("empty", b"").1, b"");
+        let (report, patched) = patch("tiny", b"cd");
+        assert_eq!(patched, b"cd");
+        assert!(report.markers.is_empty());
+    }
+
+    #[test]
+    fn test_neutralize_body() {
+        let body = b"{window.cdc_adoQpoasnfa76pfcZLmcfl_Array=window.Array;window.cdc_adoQpoasnfa76pfcZLmcfl_Symbol=window.Symbol;}";
+        // Stray token outside of the body is left as is
+        let original = [
+            b"\x00function(){".as_slice(),
+            body,
+            b"}\x00cdc_asdjflasutopfhvcZLmcfl_\x00",
+        ]
+        .concat();
+        let dir = TestDir::new("patch-neutralize");
+        let driver = dir.write("neutralize", &original);
+        let patched_path = driver.with_file_name("neutralize_PATCHED");
+        let config = PatchConfig::new().set_strategy(PatchStrategy::NeutralizeBody);
+        let report = patch_chromedriver(&driver, &patched_path, &config).unwrap();
+        let patched = std::fs::read(&patched_path).unwrap();
+
+        assert_eq!(report.strategy, PatchStrategy::NeutralizeBody);
+        assert_eq!(report.markers.len(), 1);
+        assert_eq!(report.markers[0].offset, 12);
+        assert_eq!(report.markers[0].original.as_bytes(), body);
+        assert_eq!(patched.len(), original.len());
+        assert_eq!(&patched[..12], b"\x00function(){");
+        assert_eq!(patched[12], b'{');
+        assert!(patched[13..12 + body.len() - 1].iter().all(|&b| b == b' '));
+        assert_eq!(
+            &patched[12 + body.len() - 1..],
+            b"}}\x00cdc_asdjflasutopfhvcZLmcfl_\x00"
+        );
+
+        let verification = verify_patched(&patched_path, &config).unwrap();
+        assert!(verification.is_patched());
+        assert!(verification.patched.is_empty());
+        let verification = verify_patched(&driver, &config).unwrap();
+        assert_eq!(verification.unpatched, [12]);
+    }
+
+    #[test]
+    fn test_seeded_patch() {
+        let config = PatchConfig::new().set_replacement(TokenReplacement::Seed(42));
+        let (first, patched) = patch_with("seed-1", DRIVER, &config);
+        let (second, _) = patch_with("seed-2", DRIVER, &config);
+        assert_eq!(first.output_sha256, second.output_sha256);
+        // Every marker gets a different token
+        assert_ne!(first.markers[0].replacement, first.markers[1].replacement);
+        assert!(patched[13..31].iter().all(u8::is_ascii_alphabetic));
+
+        let config = PatchConfig::new().set_replacement(TokenReplacement::Seed(43));
+        let (other, _) = patch_with("seed-3", DRIVER, &config);
+        assert_ne!(first.output_sha256, other.output_sha256);
+    }
+
+    #[test]
+    fn test_key_patch() {
+        let config =
+            PatchConfig::new().set_replacement(TokenReplacement::Key("undetected".to_string()));
+        let (report, _) = patch_with("key", DRIVER, &config);
+        assert_eq!(report.markers[0].replacement, "undetectedundetect");
+        assert_eq!(report.markers[1].replacement, "undetectedundetect");
+
+        let dir = TestDir::new("patch-invalid-key");
+        let driver = dir.write("invalid-key", DRIVER);
+        for key in ["", "cdc_1"] {
+            let config = PatchConfig::new().set_replacement(TokenReplacement::Key(key.to_string()));
+            let patched = driver.with_file_name("invalid-key_PATCHED");
+            assert!(patch_chromedriver(&driver, &patched, &config).is_err());
+        }
+    }
+
+    #[test]
+    fn test_marker_patterns() {
+        let driver = b"\x00$cdc_asdjflasutopfhvcZLmcfl_\x00cdc_adoQpoasnfa76pfcZLmcfl_Array";
+        let config =
+            PatchConfig::new().add_pattern(MarkerPattern::new("$cdc_", 10).set_charset("xyz"));
+        let dir = TestDir::new("patch-patterns");
+        let path = dir.write("patterns", driver);
+        let patched_path = path.with_file_name("patterns_PATCHED");
+        let report = patch_chromedriver(&path, &patched_path, &config).unwrap();
+        let patched = std::fs::read(&patched_path).unwrap();
+        assert_eq!(patched.len(), driver.len());
+
+        // Default marker inside of the `$cdc_` marker is patched only once
+        let matched = report
+            .markers
+            .iter()
+            .map(|m| (m.pattern.as_str(), m.offset))
+            .collect::<Vec<_>>();
+        assert_eq!(matched, [("$cdc_", 1), ("cdc_", 30)]);
+        assert!(patched[6..16].iter().all(|b| b"xyz".contains(b)));
+        assert_eq!(&patched[16..34], b"opfhvcZLmcfl_\x00cdc_");
+        assert!(patched[34..52].iter().all(u8::is_ascii_alphabetic));
+
+        assert!(verify_patched(&patched_path, &config).unwrap().is_patched());
+        assert!(!verify_patched(&path, &config).unwrap().is_patched());
+
+        let config = PatchConfig::new().set_patterns(vec![MarkerPattern::new("", 10)]);
+        assert!(patch_chromedriver(&path, &patched_path, &config).is_err());
+    }
+
+    #[test]
+    fn test_verify_patched() {
+        let dir = TestDir::new("patch-verify");
+        let driver = dir.write("verify", DRIVER);
+        let verification = verify_patched(&driver, &PatchConfig::new()).unwrap();
+        assert!(!verification.is_patched());
+        assert_eq!(verification.unpatched, [9, 43]);
+
+        let patched = driver.with_file_name("verify_PATCHED");
+        patch_chromedriver(&driver, &patched, &PatchConfig::new()).unwrap();
+        let verification = verify_patched(&patched, &PatchConfig::new()).unwrap();
+        assert!(verification.is_patched());
+        assert_eq!(verification.patched, [9, 43]);
+    }
+}
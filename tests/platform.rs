@@ -0,0 +1,43 @@
+#[cfg(test)]
+mod tests {
+    use undetected_chromedriver::Platform;
+
+    #[test]
+    fn test_from_os_arch() {
+        assert_eq!(
+            Platform::from_os_arch("linux", "x86_64").unwrap(),
+            Platform::Linux64
+        );
+        assert_eq!(
+            Platform::from_os_arch("linux", "aarch64").unwrap(),
+            Platform::LinuxArm64
+        );
+        assert_eq!(
+            Platform::from_os_arch("macos", "aarch64").unwrap().as_str(),
+            "mac-arm64"
+        );
+        assert_eq!(
+            Platform::from_os_arch("macos", "x86_64").unwrap().as_str(),
+            "mac-x64"
+        );
+        assert_eq!(
+            Platform::from_os_arch("windows", "x86").unwrap().as_str(),
+            "win32"
+        );
+        assert_eq!(
+            Platform::from_os_arch("windows", "x86_64")
+                .unwrap()
+                .as_str(),
+            "win64"
+        );
+        assert!(Platform::from_os_arch("freebsd", "x86_64").is_err());
+        assert!(Platform::from_os_arch("linux", "riscv64").is_err());
+    }
+
+    #[test]
+    fn test_chrome_for_testing_availability() {
+        assert!(Platform::Linux64.has_chrome_for_testing());
+        assert!(Platform::MacArm64.has_chrome_for_testing());
+        assert!(!Platform::LinuxArm64.has_chrome_for_testing());
+    }
+}
@@ -0,0 +1,30 @@
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+    use undetected_chromedriver::PortAllocation;
+
+    #[test]
+    fn test_os_port() {
+        let port = PortAllocation::Os.free_port().unwrap();
+        assert_ne!(port, 0);
+        TcpListener::bind(("127.0.0.1", port)).unwrap();
+    }
+
+    #[test]
+    fn test_range_port() {
+        let taken = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let taken_port = taken.local_addr().unwrap().port();
+        assert!(PortAllocation::Range(taken_port..=taken_port)
+            .free_port()
+            .is_err());
+
+        let range = 20000..=20100;
+        for _ in 0..10 {
+            let port = PortAllocation::Range(range.clone()).free_port().unwrap();
+            assert!(range.contains(&port));
+        }
+        #[allow(clippy::reversed_empty_ranges)]
+        let empty = 20100..=20000;
+        assert!(PortAllocation::Range(empty).free_port().is_err());
+    }
+}
@@ -0,0 +1,327 @@
+mod common;
+
+#[cfg(test)]
+#[cfg(unix)]
+mod tests {
+    use crate::common::{fake_chrome_builder, TestDir};
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::{TcpListener, TcpStream},
+        path::Path,
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    };
+    use undetected_chromedriver::thirtyfour::ChromiumLikeCapabilities;
+    use undetected_chromedriver::{
+        BrowserFlavor, ChromeDriverBuilder, ChromeDriverExited, ChromeDriverNotReady,
+        ChromeVersion, DefaultCapabilitiesBuilder, LogConfig, LogLevel, Platform,
+    };
+
+    /// Builder starting chromedriver `script` cached in `dir` for a fake Chrome.
+    fn builder(dir: &TestDir, script: &str) -> ChromeDriverBuilder {
+        let (builder, cached) = fake_chrome_builder(dir);
+        dir.write_script(cached.patched, script);
+        builder.set_skip_chromedriver_check(true)
+    }
+
+    /// Script writing port of a fake chromedriver to `port_file`,
+    /// which is served by [`serve_webdriver`].
+    fn write_port(port_file: &Path) -> String {
+        format!(
+            "for arg in \"$@\"; do
+  case $arg in --port=*) port=${{arg#--port=}} ;; esac
+done
+echo $port > '{}.tmp' && mv '{0}.tmp' '{0}'",
+            port_file.display()
+        )
+    }
+
+    /// Script of a fake chromedriver started on the port served by [`serve_webdriver`].
+    fn fake_chromedriver(port_file: &Path) -> String {
+        format!(
+            "{}\necho \"ChromeDriver was started successfully on port $port.\"\nexec sleep 30",
+            write_port(port_file)
+        )
+    }
+
+    const PORT_TAKEN: &str =
+        "echo '[1700000000.123][SEVERE]: bind() failed: Address already in use (98)' >&2
+echo 'Port not available. Exiting...'
+exit 1";
+
+    /// Serves WebDriver endpoints on the port written to `port_file`.
+    /// `/shutdown` is never answered.
+    /// Returns bodies of new session requests.
+    fn serve_webdriver(port_file: &Path) -> Arc<Mutex<Vec<String>>> {
+        let port_file = port_file.to_path_buf();
+        let sessions = Arc::new(Mutex::new(Vec::new()));
+        let requests = sessions.clone();
+        std::thread::spawn(move || {
+            let port = loop {
+                match std::fs::read_to_string(&port_file) {
+                    Ok(port) => break port.trim().parse::<u16>().unwrap(),
+                    Err(_) => std::thread::sleep(Duration::from_millis(10)),
+                }
+            };
+            let listener = TcpListener::bind(("127.0.0.1", port)).unwrap();
+            for stream in listener.incoming() {
+                let requests = requests.clone();
+                std::thread::spawn(move || handle_webdriver_request(stream.unwrap(), &requests));
+            }
+        });
+        sessions
+    }
+
+    fn handle_webdriver_request(mut stream: TcpStream, sessions: &Mutex<Vec<String>>) {
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).unwrap();
+        let mut content_length = 0;
+        loop {
+            let mut header = String::new();
+            reader.read_line(&mut header).unwrap();
+            if header.trim().is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse().unwrap();
+                }
+            }
+        }
+        let mut request_body = vec![0; content_length];
+        reader.read_exact(&mut request_body).unwrap();
+        let body = match request_line.split_whitespace().nth(1).unwrap() {
+            "/status" => r#"{"value":{"ready":true,"message":""}}"#,
+            "/session" => {
+                let request_body = String::from_utf8_lossy(&request_body).into_owned();
+                sessions.lock().unwrap().push(request_body);
+                r#"{"value":{"sessionId":"fake","capabilities":{"browserName":"chrome"}}}"#
+            }
+            "/shutdown" => {
+                std::thread::sleep(Duration::from_secs(30));
+                return;
+            }
+            _ => r#"{"value":null}"#,
+        };
+        let _ = write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_exited_chromedriver() {
+        let dir = TestDir::new("spawn-exited");
+        let builder = builder(&dir, "echo 'Fake chromedriver failed'\nexit 3");
+        let err = builder
+            .try_start(DefaultCapabilitiesBuilder::new().into_chrome_caps().into())
+            .await
+            .unwrap_err();
+        let exited = err.downcast_ref::<ChromeDriverExited>().unwrap();
+        assert_eq!(exited.status.code(), Some(3));
+        assert_eq!(exited.output, ["Fake chromedriver failed"]);
+    }
+
+    #[tokio::test]
+    async fn test_chromedriver_log() {
+        let script = "echo \"$@\"\necho '[1700000000.123][SEVERE]: bind() failed' >&2\nprintf 'bad \\377\\n' >&2\nexit 1";
+        let dir = TestDir::new("spawn-log");
+        let logged = builder(&dir, script).set_log_config(
+            LogConfig::new()
+                .set_log_path(Some("/tmp/chromedriver.log".into()))
+                .set_log_level(Some(LogLevel::Debug))
+                .set_verbose(true)
+                .set_append_log(true),
+        );
+        let err = logged
+            .try_start(DefaultCapabilitiesBuilder::new().into_chrome_caps().into())
+            .await
+            .unwrap_err();
+        let mut output = err
+            .downcast_ref::<ChromeDriverExited>()
+            .unwrap()
+            .output
+            .clone();
+        // Stdout and stderr are read concurrently
+        output.sort();
+        assert_eq!(output.len(), 3);
+        assert!(output[0].ends_with(
+            " --log-path=/tmp/chromedriver.log --log-level=DEBUG --verbose --append-log"
+        ));
+        assert_eq!(output[1], "[1700000000.123][SEVERE]: bind() failed");
+        assert_eq!(output[2], "bad \u{FFFD}");
+
+        let dir = TestDir::new("spawn-kept-lines");
+        let kept_lines =
+            builder(&dir, "seq 1 10\nexit 1").set_log_config(LogConfig::new().set_kept_lines(3));
+        let err = kept_lines
+            .try_start(DefaultCapabilitiesBuilder::new().into_chrome_caps().into())
+            .await
+            .unwrap_err();
+        let exited = err.downcast_ref::<ChromeDriverExited>().unwrap();
+        assert_eq!(exited.output, ["8", "9", "10"]);
+    }
+
+    #[tokio::test]
+    async fn test_not_ready_chromedriver() {
+        let dir = TestDir::new("spawn-not-ready");
+        let builder = builder(&dir, "exec sleep 30").set_ready_timeout(Duration::from_millis(500));
+        let err = builder
+            .try_start(DefaultCapabilitiesBuilder::new().into_chrome_caps().into())
+            .await
+            .unwrap_err();
+        let not_ready = err.downcast_ref::<ChromeDriverNotReady>().unwrap();
+        assert_eq!(not_ready.timeout, Duration::from_millis(500));
+    }
+
+    #[tokio::test]
+    async fn test_port_taken_retried() {
+        let dir = TestDir::new("spawn-port-taken-retried");
+        let port_file = dir.join("port");
+        let state = dir.join("state");
+        serve_webdriver(&port_file);
+        // Port is taken only on the first start
+        let script = format!(
+            "if [ ! -e '{0}' ]; then\ntouch '{0}'\n{PORT_TAKEN}\nfi\n{1}",
+            state.display(),
+            fake_chromedriver(&port_file)
+        );
+        let builder = builder(&dir, &script).set_shutdown_timeout(Duration::from_millis(100));
+        let (_driver, process) = builder
+            .try_start(DefaultCapabilitiesBuilder::new().into_chrome_caps().into())
+            .await
+            .unwrap();
+        assert!(state.exists());
+        assert_eq!(
+            std::fs::read_to_string(&port_file).unwrap().trim(),
+            process.port().to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_port_always_taken() {
+        let dir = TestDir::new("spawn-port-always-taken");
+        let err = builder(&dir, PORT_TAKEN)
+            .try_start(DefaultCapabilitiesBuilder::new().into_chrome_caps().into())
+            .await
+            .unwrap_err();
+        // Error of the last attempt is returned
+        let exited = err.downcast_ref::<ChromeDriverExited>().unwrap();
+        assert!(exited
+            .output
+            .contains(&"Port not available. Exiting...".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_foreign_status_ignored() {
+        let dir = TestDir::new("spawn-foreign-status");
+        let port_file = dir.join("port");
+        serve_webdriver(&port_file);
+        // Another process answers on the port, but chromedriver never reports it was started
+        let script = format!("{}\nexec sleep 30", write_port(&port_file));
+        let builder = builder(&dir, &script).set_ready_timeout(Duration::from_millis(500));
+        let err = builder
+            .try_start(DefaultCapabilitiesBuilder::new().into_chrome_caps().into())
+            .await
+            .unwrap_err();
+        assert!(err.is::<ChromeDriverNotReady>());
+    }
+
+    #[tokio::test]
+    async fn test_log_level_off() {
+        let dir = TestDir::new("spawn-log-level-off");
+        let port_file = dir.join("port");
+        serve_webdriver(&port_file);
+        // Chromedriver doesn't report it was started with logging off
+        let script = format!("{}\nexec sleep 30", write_port(&port_file));
+        let builder = builder(&dir, &script)
+            .set_log_config(LogConfig::new().set_log_level(Some(LogLevel::Off)))
+            .set_ready_timeout(Duration::from_secs(5))
+            .set_shutdown_timeout(Duration::from_millis(100));
+        builder
+            .try_start(DefaultCapabilitiesBuilder::new().into_chrome_caps().into())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_capabilities_binary() {
+        let dir = TestDir::new("spawn-capabilities-binary");
+        let port_file = dir.join("port");
+        let sessions = serve_webdriver(&port_file);
+        // Builder detects Chrome 131 by default
+        let builder = builder(&dir, "exit 1").set_shutdown_timeout(Duration::from_millis(100));
+        let chrome = dir.write_script("chrome-130", "echo 'Google Chrome 130.0.6723.116'");
+        let patched = builder.cache().patched_driver_path(
+            BrowserFlavor::Chrome,
+            &ChromeVersion::new(130, 0, 6723, 116),
+            Platform::current().unwrap(),
+        );
+        dir.write_script(patched, &fake_chromedriver(&port_file));
+
+        let mut capabilities = DefaultCapabilitiesBuilder::new().into_chrome_caps();
+        capabilities.set_binary(chrome.to_str().unwrap()).unwrap();
+        builder.try_start(capabilities.into()).await.unwrap();
+        // Chromedriver for the browser set by the caller is started, the browser is kept
+        let session = sessions.lock().unwrap()[0].clone();
+        assert!(session.contains(&format!(r#""binary":"{}""#, chrome.display())));
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_timeout() {
+        let dir = TestDir::new("spawn-shutdown-timeout");
+        let port_file = dir.join("port");
+        serve_webdriver(&port_file);
+        let builder = builder(&dir, &fake_chromedriver(&port_file))
+            .set_shutdown_timeout(Duration::from_millis(500));
+        let (driver, process) = builder
+            .try_start(DefaultCapabilitiesBuilder::new().into_chrome_caps().into())
+            .await
+            .unwrap();
+        drop(driver);
+
+        // Unanswered shutdown request and exit wait share the timeout
+        let start = Instant::now();
+        process.shutdown().await.unwrap();
+        assert!(start.elapsed() < Duration::from_millis(900));
+    }
+
+    #[tokio::test]
+    #[cfg(target_os = "linux")]
+    async fn test_process_group_killed() {
+        let dir = TestDir::new("spawn-process-group");
+        let pid_file = dir.join("browser.pid");
+        let script = format!(
+            "sleep 30 &\necho $! > '{}'\nexec sleep 30",
+            pid_file.display()
+        );
+        let builder = builder(&dir, &script).set_ready_timeout(Duration::from_millis(500));
+        builder
+            .try_start(DefaultCapabilitiesBuilder::new().into_chrome_caps().into())
+            .await
+            .unwrap_err();
+
+        // Process started by chromedriver (like a browser) is killed with it
+        let pid = std::fs::read_to_string(&pid_file).unwrap();
+        // Killed process can stay a zombie until it is reaped by init
+        let is_running = || {
+            let output = std::process::Command::new("ps")
+                .args(["-o", "stat=", "-p", pid.trim()])
+                .output()
+                .unwrap();
+            output.status.success()
+                && !String::from_utf8_lossy(&output.stdout)
+                    .trim()
+                    .starts_with('Z')
+        };
+        for _ in 0..20 {
+            if !is_running() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert!(!is_running());
+    }
+}